@@ -1,48 +1,253 @@
 use std::env;
 use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
 use std::str::FromStr;
 
+const USAGE: &str = "Usage: day01 [--dial-size SIZE] [--initial-position POSITION[,POSITION...]] [--trace table|csv] [--verify] INPUT_FILE_PATH | day01 [--dial-size SIZE] [--initial-position POSITION] --synthesize STOPS PASSES";
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
+    let mut args = env::args().skip(1);
 
-    if let Some(path) = args.get(1) {
-        let safe = Safe::try_from(BufReader::new(File::open(path)?))?;
+    let mut path = None;
+    let mut dial_size = DEFAULT_DIAL_SIZE;
+    let mut initial_positions = vec![DEFAULT_INITIAL_POSITION];
+    let mut trace_format = None;
+    let mut verify = false;
+    let mut synthesize = None;
 
-        println!("Password counting stops on zero: {}", safe.stops_password());
-        println!(
-            "Password counting passes by zero: {}",
-            safe.passes_password()
-        );
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dial-size" => dial_size = args.next().ok_or(USAGE)?.parse()?,
+            "--initial-position" => {
+                initial_positions = args
+                    .next()
+                    .ok_or(USAGE)?
+                    .split(',')
+                    .map(|position| position.parse())
+                    .collect::<Result<_, _>>()?
+            }
+            "--trace" => trace_format = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--verify" => verify = true,
+            "--synthesize" => {
+                synthesize = Some((
+                    args.next().ok_or(USAGE)?.parse()?,
+                    args.next().ok_or(USAGE)?.parse()?,
+                ))
+            }
+            _ => path = Some(arg),
+        }
+    }
+
+    let multi_dial = initial_positions.len() > 1;
+
+    if let Some((stops_password, passes_password)) = synthesize {
+        if multi_dial {
+            return Err("Synthesized rotations only support a single dial".into());
+        }
+
+        let safe = Safe::synthesize(
+            dial_size,
+            initial_positions[0],
+            stops_password,
+            passes_password,
+        )?;
+
+        for rotation in safe.rotations() {
+            println!("{}", rotation);
+        }
+
+        Ok(())
+    } else if let Some(path) = path {
+        if trace_format.is_none() && !verify && !multi_dial {
+            // Nothing needs the full list of rotations, so we can skip buffering it
+            let evaluator = SafeEvaluator::new(dial_size, initial_positions[0])?
+                .evaluate(BufReader::new(File::open(path)?))?;
+
+            println!(
+                "Password counting stops on zero: {}",
+                evaluator.stops_password()
+            );
+            println!(
+                "Password counting passes by zero: {}",
+                evaluator.passes_password()
+            );
+
+            return Ok(());
+        }
+
+        let safe = Safe::try_from(BufReader::new(File::open(path)?))?
+            .with_dials(dial_size, &initial_positions)?;
+
+        if let Some(trace_format) = trace_format {
+            safe.write_trace(std::io::stdout().lock(), trace_format)?;
+            println!();
+        }
+
+        if multi_dial {
+            for (dial, (stops_password, passes_password)) in safe
+                .stops_passwords()
+                .iter()
+                .zip(safe.passes_passwords())
+                .enumerate()
+            {
+                println!(
+                    "Dial {} password counting stops on zero: {}",
+                    dial, stops_password
+                );
+                println!(
+                    "Dial {} password counting passes by zero: {}",
+                    dial, passes_password
+                );
+            }
+        } else {
+            println!("Password counting stops on zero: {}", safe.stops_password());
+            println!(
+                "Password counting passes by zero: {}",
+                safe.passes_password()
+            );
+        }
+
+        if verify {
+            let simulated_passes_password = safe.simulated_passes_password();
+
+            if simulated_passes_password != safe.passes_password() {
+                return Err(format!(
+                    "Simulated password counting passes by zero disagrees: {}",
+                    simulated_passes_password
+                )
+                .into());
+            }
+        }
 
         Ok(())
     } else {
-        Err("Usage: day01 INPUT_FILE_PATH".into())
+        Err(USAGE.into())
     }
 }
 
+const DEFAULT_DIAL_SIZE: u32 = 100;
+const DEFAULT_INITIAL_POSITION: u32 = 50;
+
 struct Safe {
     rotations: Vec<Rotation>,
+    dial_size: u32,
+    // Initial positions of each dial, starting with the innermost
+    initial_positions: Vec<u32>,
 }
 
 impl Safe {
     pub fn try_from(reader: impl BufRead) -> Result<Self, Box<dyn Error>> {
-        let rotations: Vec<Rotation> = reader
-            .lines()
-            .map_while(|result| result.ok())
-            .map(|line| Rotation::from_str(&line))
-            .collect::<Result<Vec<_>, _>>()?;
+        let rotations: Vec<Rotation> = parse_rotations(reader).collect::<Result<Vec<_>, _>>()?;
 
-        Ok(Safe { rotations })
+        Ok(Safe {
+            rotations,
+            dial_size: DEFAULT_DIAL_SIZE,
+            initial_positions: vec![DEFAULT_INITIAL_POSITION],
+        })
     }
 
-    pub fn stops_password(&self) -> u32 {
-        let mut position = 50;
+    pub fn with_dials(
+        self,
+        dial_size: u32,
+        initial_positions: &[u32],
+    ) -> Result<Self, Box<dyn Error>> {
+        if initial_positions.is_empty() {
+            return Err("Safe must have at least one dial".into());
+        }
+
+        for initial_position in initial_positions {
+            validate_dial(dial_size, *initial_position)?;
+        }
+
+        // Keep the combined reading of all dials within a u64 so we can do carry arithmetic on a
+        // single number
+        u32::try_from(initial_positions.len())
+            .ok()
+            .and_then(|dials| (dial_size as u64).checked_pow(dials))
+            .ok_or("Too many dials")?;
+
+        Ok(Safe {
+            dial_size,
+            initial_positions: initial_positions.to_vec(),
+            ..self
+        })
+    }
+
+    pub fn synthesize(
+        dial_size: u32,
+        initial_position: u32,
+        stops_password: u32,
+        passes_password: u32,
+    ) -> Result<Self, Box<dyn Error>> {
+        validate_dial(dial_size, initial_position)?;
+
+        let too_large = || "Password is too large to synthesize for this dial";
+        let mut rotations = Vec::new();
+
+        if stops_password == 0 {
+            if passes_password > 0 {
+                if dial_size == 1 {
+                    return Err("Every rotation of a single-position dial stops on zero".into());
+                }
+
+                // Spin past zero as many times as needed, then stop just beyond it
+                rotations.push(Rotation::Right(
+                    passes_password
+                        .checked_mul(dial_size)
+                        .and_then(|distance| distance.checked_add(1))
+                        .and_then(|distance| distance.checked_sub(initial_position))
+                        .ok_or_else(too_large)?,
+                ));
+            }
+        } else {
+            // Getting to zero for the first time costs a pass unless we're already there
+            let initial_passes = if initial_position == 0 { 0 } else { 1 };
+
+            if passes_password < initial_passes {
+                return Err("Can't stop on zero without first passing it".into());
+            }
+
+            // Every stop after the first can be a full revolution (one pass) or a zero-length turn
+            // (no passes); whatever passes are left over get spent on the first turn
+            let remaining_passes = passes_password - initial_passes;
+            let revolutions = remaining_passes.min(stops_password - 1);
+            let first_revolutions = remaining_passes - revolutions;
+
+            rotations.push(Rotation::Left(
+                first_revolutions
+                    .checked_mul(dial_size)
+                    .and_then(|distance| distance.checked_add(initial_position))
+                    .ok_or_else(too_large)?,
+            ));
+
+            for i in 1..stops_password {
+                rotations.push(Rotation::Right(if i <= revolutions {
+                    dial_size
+                } else {
+                    0
+                }));
+            }
+        }
+
+        Ok(Safe {
+            rotations,
+            dial_size,
+            initial_positions: vec![initial_position],
+        })
+    }
+
+    pub fn rotations(&self) -> &[Rotation] {
+        &self.rotations
+    }
+
+    pub fn stops_password(&self) -> u64 {
+        let mut position = self.initial_positions[0];
         let mut password = 0;
 
         for rotation in &self.rotations {
-            position = rotation.apply(position);
+            position = rotation.apply(position, self.dial_size);
 
             if position == 0 {
                 password += 1;
@@ -52,33 +257,285 @@ impl Safe {
         password
     }
 
-    pub fn passes_password(&self) -> u32 {
-        let mut position = 50;
+    pub fn passes_password(&self) -> u64 {
+        let mut position = self.initial_positions[0];
         let mut password = 0;
 
         for rotation in &self.rotations {
-            match rotation {
-                Rotation::Left(distance) => {
-                    let position = position as i32;
+            password += rotation.zero_passes(position, self.dial_size) as u64;
+            position = rotation.apply(position, self.dial_size);
+        }
 
-                    if position - (*distance as i32) <= 0 {
-                        password += ((position - (*distance as i32)) / 100).unsigned_abs();
+        password
+    }
 
-                        if position > 0 {
-                            password += 1;
-                        }
+    pub fn stops_passwords(&self) -> Vec<u64> {
+        self.dial_passwords().0
+    }
+
+    pub fn passes_passwords(&self) -> Vec<u64> {
+        self.dial_passwords().1
+    }
+
+    fn dial_passwords(&self) -> (Vec<u64>, Vec<u64>) {
+        let dials = self.initial_positions.len();
+        let dial_size = self.dial_size as i128;
+
+        // Treat the dials like the digits of an odometer; the position of dial `i` is then
+        // `(reading / place_values[i]) % dial_size`, and the last place value is the number of
+        // clicks it takes for the whole stack of dials to come back around to where it started.
+        let place_values: Vec<i128> = (0..=dials as u32).map(|i| dial_size.pow(i)).collect();
+
+        let mut reading: i128 = self
+            .initial_positions
+            .iter()
+            .zip(&place_values)
+            .map(|(position, place_value)| *position as i128 * place_value)
+            .sum();
+
+        let mut stops_passwords = vec![0; dials];
+        let mut passes_passwords = vec![0; dials];
+
+        for rotation in &self.rotations {
+            let next_reading = match rotation {
+                Rotation::Left(distance) => reading - *distance as i128,
+                Rotation::Right(distance) => reading + *distance as i128,
+            };
+
+            for dial in 0..dials {
+                let (place_value, next_place_value) = (place_values[dial], place_values[dial + 1]);
+
+                passes_passwords[dial] += match rotation {
+                    // Turning right, a dial lands on zero whenever it and everything inside it
+                    // rolls over together
+                    Rotation::Right(_) => {
+                        count_congruent(reading, next_reading, 0, next_place_value)
                     }
+                    // Turning left, a dial lands on zero whenever it steps down from one
+                    Rotation::Left(_) => {
+                        count_congruent(next_reading, reading, place_value, next_place_value)
+                    }
+                };
+            }
+
+            reading = next_reading.rem_euclid(place_values[dials]);
+
+            for dial in 0..dials {
+                if (reading / place_values[dial]) % dial_size == 0 {
+                    stops_passwords[dial] += 1;
                 }
-                Rotation::Right(distance) => {
-                    password += (position + distance) / 100;
-                }
+            }
+        }
+
+        (stops_passwords, passes_passwords)
+    }
+
+    pub fn simulated_passes_password(&self) -> u64 {
+        let mut position = self.initial_positions[0] as u64;
+        let mut password = 0;
+
+        for rotation in &self.rotations {
+            // Turn the dial one click at a time; this is slow, but is much harder to get wrong than
+            // the arithmetic in `passes_password`
+            let (distance, step) = match rotation {
+                Rotation::Left(distance) => (*distance, self.dial_size as u64 - 1),
+                Rotation::Right(distance) => (*distance, 1),
             };
 
-            position = rotation.apply(position);
+            for _ in 0..distance {
+                position = (position + step) % self.dial_size as u64;
+
+                if position == 0 {
+                    password += 1;
+                }
+            }
         }
 
         password
     }
+
+    pub fn trace(&self) -> impl Iterator<Item = TraceStep<'_>> {
+        self.rotations
+            .iter()
+            .scan(self.initial_positions[0], |position, rotation| {
+                let start_position = *position;
+                *position = rotation.apply(start_position, self.dial_size);
+
+                Some(TraceStep {
+                    rotation,
+                    start_position,
+                    end_position: *position,
+                    zero_passes: rotation.zero_passes(start_position, self.dial_size),
+                    stopped_on_zero: *position == 0,
+                })
+            })
+    }
+
+    pub fn write_trace(
+        &self,
+        mut writer: impl Write,
+        format: TraceFormat,
+    ) -> Result<(), Box<dyn Error>> {
+        match format {
+            TraceFormat::Table => {
+                writeln!(
+                    writer,
+                    "{:>6} {:>12} {:>8} {:>8} {:>11} {:>7}",
+                    "Step", "Rotation", "Start", "End", "Zero passes", "Stopped"
+                )?;
+
+                for (i, step) in self.trace().enumerate() {
+                    writeln!(
+                        writer,
+                        "{:>6} {:>12} {:>8} {:>8} {:>11} {:>7}",
+                        i + 1,
+                        step.rotation.to_string(),
+                        step.start_position,
+                        step.end_position,
+                        step.zero_passes,
+                        if step.stopped_on_zero { "yes" } else { "no" },
+                    )?;
+                }
+            }
+            TraceFormat::Csv => {
+                writeln!(
+                    writer,
+                    "step,direction,distance,start_position,end_position,zero_passes,stopped_on_zero"
+                )?;
+
+                for (i, step) in self.trace().enumerate() {
+                    let (direction, distance) = match step.rotation {
+                        Rotation::Left(distance) => ('L', distance),
+                        Rotation::Right(distance) => ('R', distance),
+                    };
+
+                    writeln!(
+                        writer,
+                        "{},{},{},{},{},{},{}",
+                        i + 1,
+                        direction,
+                        distance,
+                        step.start_position,
+                        step.end_position,
+                        step.zero_passes,
+                        step.stopped_on_zero
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_rotations(reader: impl BufRead) -> impl Iterator<Item = Result<Rotation, Box<dyn Error>>> {
+    reader.lines().enumerate().filter_map(|(index, line)| {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => return Some(Err(error.into())),
+        };
+
+        // Everything after a '#' is a comment, and lines with nothing left are skipped entirely
+        let rotation = line.split('#').next().unwrap_or_default().trim();
+
+        if rotation.is_empty() {
+            None
+        } else {
+            Some(
+                Rotation::from_str(rotation)
+                    .map_err(|error| format!("Line {}: {}", index + 1, error).into()),
+            )
+        }
+    })
+}
+
+// Counts the integers in `(lower, upper]` that are congruent to `residue` modulo `modulus`
+fn count_congruent(lower: i128, upper: i128, residue: i128, modulus: i128) -> u64 {
+    ((upper - residue).div_euclid(modulus) - (lower - residue).div_euclid(modulus)) as u64
+}
+
+fn validate_dial(dial_size: u32, initial_position: u32) -> Result<(), Box<dyn Error>> {
+    if dial_size == 0 {
+        return Err("Dial must have at least one position".into());
+    }
+
+    if initial_position >= dial_size {
+        return Err("Initial position must be on the dial".into());
+    }
+
+    Ok(())
+}
+
+struct SafeEvaluator {
+    dial_size: u32,
+    position: u32,
+    stops_password: u64,
+    passes_password: u64,
+}
+
+impl SafeEvaluator {
+    pub fn new(dial_size: u32, initial_position: u32) -> Result<Self, Box<dyn Error>> {
+        validate_dial(dial_size, initial_position)?;
+
+        Ok(SafeEvaluator {
+            dial_size,
+            position: initial_position,
+            stops_password: 0,
+            passes_password: 0,
+        })
+    }
+
+    pub fn evaluate(mut self, reader: impl BufRead) -> Result<Self, Box<dyn Error>> {
+        for rotation in parse_rotations(reader) {
+            self.rotate(&rotation?);
+        }
+
+        Ok(self)
+    }
+
+    pub fn rotate(&mut self, rotation: &Rotation) {
+        self.passes_password += rotation.zero_passes(self.position, self.dial_size) as u64;
+        self.position = rotation.apply(self.position, self.dial_size);
+
+        if self.position == 0 {
+            self.stops_password += 1;
+        }
+    }
+
+    pub fn stops_password(&self) -> u64 {
+        self.stops_password
+    }
+
+    pub fn passes_password(&self) -> u64 {
+        self.passes_password
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct TraceStep<'a> {
+    rotation: &'a Rotation,
+    start_position: u32,
+    end_position: u32,
+    zero_passes: u32,
+    stopped_on_zero: bool,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum TraceFormat {
+    Table,
+    Csv,
+}
+
+impl FromStr for TraceFormat {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(TraceFormat::Table),
+            "csv" => Ok(TraceFormat::Csv),
+            _ => Err("Trace format must be either \"table\" or \"csv\"".into()),
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -88,13 +545,46 @@ enum Rotation {
 }
 
 impl Rotation {
-    pub fn apply(&self, position: u32) -> u32 {
+    pub fn apply(&self, position: u32, dial_size: u32) -> u32 {
         match self {
             Rotation::Left(distance) => {
-                let position = position as i32;
-                (((position - ((distance % 100) as i32)) + 100) % 100) as u32
+                let (position, distance, dial_size) =
+                    (position as u64, *distance as u64, dial_size as u64);
+
+                ((position + dial_size - (distance % dial_size)) % dial_size) as u32
+            }
+            Rotation::Right(distance) => {
+                ((position as u64 + *distance as u64) % dial_size as u64) as u32
+            }
+        }
+    }
+
+    pub fn zero_passes(&self, position: u32, dial_size: u32) -> u32 {
+        match self {
+            Rotation::Left(distance) => {
+                if position == 0 {
+                    // We're starting on zero, so we'll need a full revolution to get back to it
+                    distance / dial_size
+                } else if *distance >= position {
+                    // We'll hit zero once on the way down, then once more for every full revolution
+                    // after that
+                    1 + (distance - position) / dial_size
+                } else {
+                    0
+                }
+            }
+            Rotation::Right(distance) => {
+                ((position as u64 + *distance as u64) / dial_size as u64) as u32
             }
-            Rotation::Right(distance) => (position + distance) % 100,
+        }
+    }
+}
+
+impl Display for Rotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rotation::Left(distance) => write!(f, "L{}", distance),
+            Rotation::Right(distance) => write!(f, "R{}", distance),
         }
     }
 }
@@ -103,19 +593,34 @@ impl FromStr for Rotation {
     type Err = Box<dyn Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let magnitude: u32 = s[1..].parse()?;
+        let s = s.trim();
+        let mut chars = s.chars();
+
+        let direction = chars.next().ok_or("Rotation string must not be empty")?;
+        let magnitude = chars.as_str().trim_start();
 
-        match s.chars().next() {
-            Some('L') => Ok(Rotation::Left(magnitude)),
-            Some('R') => Ok(Rotation::Right(magnitude)),
-            _ => Err("Could not parse rotation string".into()),
+        // `u32::from_str` would happily take a sign of its own, but "L+5" and friends are almost
+        // certainly typos
+        if !magnitude.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(format!("Could not parse rotation distance in \"{}\"", s).into());
+        }
+
+        let magnitude: u32 = magnitude.parse().map_err(|error| {
+            format!("Could not parse rotation distance in \"{}\": {}", s, error)
+        })?;
+
+        match direction {
+            'L' | 'l' | '-' => Ok(Rotation::Left(magnitude)),
+            'R' | 'r' | '+' => Ok(Rotation::Right(magnitude)),
+            _ => Err(format!("Could not parse rotation direction in \"{}\"", s).into()),
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{Rotation, Safe};
+    use crate::{Rotation, Safe, SafeEvaluator, TraceFormat, TraceStep};
+    use advent_of_code_2025::random;
     use indoc::indoc;
     use std::io::Cursor;
     use std::str::FromStr;
@@ -138,16 +643,109 @@ mod test {
         assert_eq!(Rotation::Left(68), Rotation::from_str("L68").unwrap());
         assert_eq!(Rotation::Right(14), Rotation::from_str("R14").unwrap());
         assert!(Rotation::from_str("Not a rotation").is_err());
+
+        assert_eq!(Rotation::Left(68), Rotation::from_str("l68").unwrap());
+        assert_eq!(Rotation::Right(14), Rotation::from_str("  r 14\t").unwrap());
+        assert_eq!(Rotation::Right(12), Rotation::from_str("+12").unwrap());
+        assert_eq!(Rotation::Left(7), Rotation::from_str("-7").unwrap());
+
+        for malformed in [
+            "",
+            " ",
+            "L",
+            "R-5",
+            "L+5",
+            "X12",
+            "12",
+            "L12x",
+            "L99999999999",
+            "é12",
+            "Ł12",
+            "L١٢",
+        ] {
+            assert!(Rotation::from_str(malformed).is_err(), "{:?}", malformed);
+        }
+    }
+
+    #[test]
+    fn test_safe_try_from_extended_syntax() {
+        let safe = Safe::try_from(Cursor::new(
+            indoc! {"
+                # Rotations from the puzzle example, with some decoration
+                L68
+                  l30
+
+                +48  # Same as R48
+                -5
+                r60
+                L 55
+                L1
+                L99
+                R14
+                L82
+            "}
+            .as_bytes(),
+        ))
+        .unwrap();
+
+        assert_eq!(3, safe.stops_password());
+        assert_eq!(6, safe.passes_password());
+
+        let error = Safe::try_from(Cursor::new("L68\n\n# Comment\nX12\n".as_bytes()))
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().starts_with("Line 4: "), "{}", error);
+
+        let error = SafeEvaluator::new(100, 50)
+            .unwrap()
+            .evaluate(Cursor::new("L68\nL\n".as_bytes()))
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().starts_with("Line 2: "), "{}", error);
+    }
+
+    #[test]
+    fn test_rotation_display() {
+        assert_eq!("L68", Rotation::Left(68).to_string());
+        assert_eq!("R0", Rotation::Right(0).to_string());
+
+        for rotation in [Rotation::Left(5008), Rotation::Right(14)] {
+            assert_eq!(rotation, Rotation::from_str(&rotation.to_string()).unwrap());
+        }
     }
 
     #[test]
     fn test_rotation_apply() {
-        assert_eq!(19, Rotation::Right(8).apply(11));
-        assert_eq!(0, Rotation::Left(19).apply(19));
-        assert_eq!(99, Rotation::Left(1).apply(0));
-        assert_eq!(0, Rotation::Right(1).apply(99));
-        assert_eq!(19, Rotation::Right(5008).apply(11));
-        assert_eq!(7, Rotation::Left(402).apply(9));
+        assert_eq!(19, Rotation::Right(8).apply(11, 100));
+        assert_eq!(0, Rotation::Left(19).apply(19, 100));
+        assert_eq!(99, Rotation::Left(1).apply(0, 100));
+        assert_eq!(0, Rotation::Right(1).apply(99, 100));
+        assert_eq!(19, Rotation::Right(5008).apply(11, 100));
+        assert_eq!(7, Rotation::Left(402).apply(9, 100));
+
+        assert_eq!(2, Rotation::Right(5).apply(7, 10));
+        assert_eq!(1, Rotation::Left(13).apply(2, 12));
+        assert_eq!(0, Rotation::Left(u32::MAX).apply(0, 1));
+        assert_eq!(
+            u32::MAX - 1,
+            Rotation::Right(u32::MAX).apply(u32::MAX - 1, u32::MAX)
+        );
+    }
+
+    #[test]
+    fn test_rotation_zero_passes() {
+        assert_eq!(0, Rotation::Right(8).zero_passes(11, 100));
+        assert_eq!(1, Rotation::Left(19).zero_passes(19, 100));
+        assert_eq!(0, Rotation::Left(1).zero_passes(0, 100));
+        assert_eq!(1, Rotation::Left(100).zero_passes(0, 100));
+        assert_eq!(50, Rotation::Right(5008).zero_passes(11, 100));
+        assert_eq!(4, Rotation::Left(402).zero_passes(9, 100));
+
+        assert_eq!(1, Rotation::Right(5).zero_passes(7, 10));
+        assert_eq!(2, Rotation::Left(13).zero_passes(2, 10));
+        assert_eq!(7, Rotation::Left(7).zero_passes(0, 1));
     }
 
     #[test]
@@ -163,4 +761,401 @@ mod test {
 
         assert_eq!(6, safe.passes_password());
     }
+
+    #[test]
+    fn test_safe_trace() {
+        let safe = Safe::try_from(Cursor::new(TEST_SAFE.as_bytes())).unwrap();
+        let trace: Vec<TraceStep> = safe.trace().collect();
+
+        assert_eq!(10, trace.len());
+
+        assert_eq!(
+            TraceStep {
+                rotation: &Rotation::Left(68),
+                start_position: 50,
+                end_position: 82,
+                zero_passes: 1,
+                stopped_on_zero: false,
+            },
+            trace[0]
+        );
+
+        assert_eq!(
+            TraceStep {
+                rotation: &Rotation::Left(55),
+                start_position: 55,
+                end_position: 0,
+                zero_passes: 1,
+                stopped_on_zero: true,
+            },
+            trace[5]
+        );
+
+        assert_eq!(
+            safe.stops_password() as usize,
+            trace.iter().filter(|step| step.stopped_on_zero).count()
+        );
+
+        assert_eq!(
+            safe.passes_password(),
+            trace
+                .iter()
+                .map(|step| step.zero_passes as u64)
+                .sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn test_safe_write_trace() {
+        let safe = Safe::try_from(Cursor::new("L68\nR18\n".as_bytes())).unwrap();
+
+        let mut csv = Vec::new();
+        safe.write_trace(&mut csv, TraceFormat::Csv).unwrap();
+
+        assert_eq!(
+            indoc! {"
+                step,direction,distance,start_position,end_position,zero_passes,stopped_on_zero
+                1,L,68,50,82,1,false
+                2,R,18,82,0,1,true
+            "},
+            String::from_utf8(csv).unwrap()
+        );
+
+        let mut table = Vec::new();
+        safe.write_trace(&mut table, TraceFormat::Table).unwrap();
+
+        assert_eq!(
+            concat!(
+                "  Step     Rotation    Start      End Zero passes Stopped\n",
+                "     1          L68       50       82           1      no\n",
+                "     2          R18       82        0           1     yes\n",
+            ),
+            String::from_utf8(table).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_safe_simulated_passes_password() {
+        let safe = Safe::try_from(Cursor::new(TEST_SAFE.as_bytes())).unwrap();
+
+        assert_eq!(6, safe.simulated_passes_password());
+
+        for (rotation, expected) in [
+            (Rotation::Right(5008), 50),
+            (Rotation::Left(5008), 50),
+            (Rotation::Left(50), 1),
+            (Rotation::Left(49), 0),
+            (Rotation::Right(49), 0),
+            (Rotation::Right(50), 1),
+            (Rotation::Left(150), 2),
+        ] {
+            let safe = Safe {
+                rotations: vec![rotation],
+                dial_size: 100,
+                initial_positions: vec![50],
+            };
+
+            assert_eq!(expected, safe.simulated_passes_password());
+            assert_eq!(expected, safe.passes_password());
+        }
+    }
+
+    #[test]
+    fn test_passes_password_differential() {
+        let mut next_random = random::generator::<u32>(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..5_000 {
+            let dial_size = match next_random(4) {
+                0 => 100,
+                1 => 1 + next_random(3),
+                _ => 1 + next_random(250),
+            };
+
+            let initial_position = next_random(dial_size);
+
+            let rotations = (0..next_random(24))
+                .map(|_| {
+                    let distance = match next_random(10) {
+                        // Mostly short turns, with occasional multi-revolution spins and the
+                        // exact-multiple cases that tend to trip up the division logic
+                        0 => next_random(10_000),
+                        1 => dial_size * next_random(5),
+                        2 => initial_position + (dial_size * next_random(3)),
+                        _ => next_random(2 * dial_size),
+                    };
+
+                    if next_random(2) == 0 {
+                        Rotation::Left(distance)
+                    } else {
+                        Rotation::Right(distance)
+                    }
+                })
+                .collect();
+
+            let safe = Safe {
+                rotations,
+                dial_size,
+                initial_positions: vec![initial_position],
+            };
+
+            assert_eq!(
+                safe.simulated_passes_password(),
+                safe.passes_password(),
+                "Password mismatch for dial size {}, initial position {}, rotations {:?}",
+                dial_size,
+                initial_position,
+                safe.rotations
+            );
+
+            let mut evaluator = SafeEvaluator::new(dial_size, initial_position).unwrap();
+            safe.rotations
+                .iter()
+                .for_each(|rotation| evaluator.rotate(rotation));
+
+            assert_eq!(safe.stops_password(), evaluator.stops_password());
+            assert_eq!(safe.passes_password(), evaluator.passes_password());
+        }
+    }
+
+    #[test]
+    fn test_safe_evaluator() {
+        let evaluator = SafeEvaluator::new(100, 50)
+            .unwrap()
+            .evaluate(Cursor::new(TEST_SAFE.as_bytes()))
+            .unwrap();
+
+        assert_eq!(3, evaluator.stops_password());
+        assert_eq!(6, evaluator.passes_password());
+
+        let evaluator = SafeEvaluator::new(10, 2)
+            .unwrap()
+            .evaluate(Cursor::new("R3\nL5\nR12\nL10\n".as_bytes()))
+            .unwrap();
+
+        assert_eq!(1, evaluator.stops_password());
+        assert_eq!(3, evaluator.passes_password());
+
+        assert!(SafeEvaluator::new(10, 10).is_err());
+        assert!(
+            SafeEvaluator::new(100, 50)
+                .unwrap()
+                .evaluate(Cursor::new("L68\nNot a rotation\n".as_bytes()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_single_position_dial_passwords() {
+        // Every click of a single-position dial lands on zero, so two maximal rotations overflow
+        // a `u32` password
+        const INPUT: &str = "L4294967295\nL4294967295\n";
+        let expected_passes = 2 * u32::MAX as u64;
+
+        let safe = Safe::try_from(Cursor::new(INPUT.as_bytes()))
+            .unwrap()
+            .with_dials(1, &[0])
+            .unwrap();
+
+        assert_eq!(2, safe.stops_password());
+        assert_eq!(expected_passes, safe.passes_password());
+        assert_eq!(vec![expected_passes], safe.passes_passwords());
+
+        let evaluator = SafeEvaluator::new(1, 0)
+            .unwrap()
+            .evaluate(Cursor::new(INPUT.as_bytes()))
+            .unwrap();
+
+        assert_eq!(2, evaluator.stops_password());
+        assert_eq!(expected_passes, evaluator.passes_password());
+
+        let safe = Safe::try_from(Cursor::new(INPUT.as_bytes()))
+            .unwrap()
+            .with_dials(1, &[0, 0])
+            .unwrap();
+
+        assert_eq!(vec![expected_passes; 2], safe.passes_passwords());
+        assert_eq!(vec![2; 2], safe.stops_passwords());
+    }
+
+    #[test]
+    fn test_safe_synthesize() {
+        for dial_size in [1, 2, 3, 10, 100] {
+            for initial_position in [0, 1, dial_size / 2, dial_size - 1] {
+                if initial_position >= dial_size {
+                    continue;
+                }
+
+                for stops_password in 0..6 {
+                    for passes_password in 0..10 {
+                        let result = Safe::synthesize(
+                            dial_size,
+                            initial_position,
+                            stops_password,
+                            passes_password,
+                        );
+
+                        let possible = if stops_password == 0 {
+                            passes_password == 0 || dial_size > 1
+                        } else {
+                            passes_password > 0 || initial_position == 0
+                        };
+
+                        if !possible {
+                            assert!(result.is_err());
+                            continue;
+                        }
+
+                        // Round-trip through the input format to make sure the synthesized
+                        // rotations would work as an input file
+                        let input: String = result
+                            .unwrap()
+                            .rotations()
+                            .iter()
+                            .map(|rotation| format!("{}\n", rotation))
+                            .collect();
+
+                        let safe = Safe::try_from(Cursor::new(input.as_bytes()))
+                            .unwrap()
+                            .with_dials(dial_size, &[initial_position])
+                            .unwrap();
+
+                        assert_eq!(stops_password as u64, safe.stops_password());
+                        assert_eq!(passes_password as u64, safe.passes_password());
+                    }
+                }
+            }
+        }
+
+        assert!(Safe::synthesize(100, 50, 0, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_safe_multi_dial_passwords() {
+        let safe = Safe::try_from(Cursor::new("R15\nL11\n".as_bytes()))
+            .unwrap()
+            .with_dials(10, &[5, 9, 0])
+            .unwrap();
+
+        // 095 -> 110 -> 099
+        assert_eq!(vec![1, 0, 1], safe.stops_passwords());
+        assert_eq!(vec![3, 2, 1], safe.passes_passwords());
+
+        // The innermost dial doesn't care what the others are doing
+        let safe = Safe::try_from(Cursor::new(TEST_SAFE.as_bytes()))
+            .unwrap()
+            .with_dials(100, &[50, 99, 0])
+            .unwrap();
+
+        assert_eq!(safe.stops_password(), safe.stops_passwords()[0]);
+        assert_eq!(safe.passes_password(), safe.passes_passwords()[0]);
+
+        assert!(
+            Safe::try_from(Cursor::new(TEST_SAFE.as_bytes()))
+                .unwrap()
+                .with_dials(100, &[])
+                .is_err()
+        );
+        assert!(
+            Safe::try_from(Cursor::new(TEST_SAFE.as_bytes()))
+                .unwrap()
+                .with_dials(100, &[0; 10])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_safe_multi_dial_differential() {
+        let mut next_random = random::generator::<u32>(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..1_000 {
+            let dial_size = 1 + next_random(12);
+            let initial_positions: Vec<u32> = (0..1 + next_random(4))
+                .map(|_| next_random(dial_size))
+                .collect();
+
+            let rotations: Vec<Rotation> = (0..next_random(12))
+                .map(|_| {
+                    let distance = next_random(3 * dial_size.pow(2));
+
+                    if next_random(2) == 0 {
+                        Rotation::Left(distance)
+                    } else {
+                        Rotation::Right(distance)
+                    }
+                })
+                .collect();
+
+            // Turn the odometer one click at a time, carrying and borrowing by hand
+            let mut positions = initial_positions.clone();
+            let mut stops_passwords = vec![0; positions.len()];
+            let mut passes_passwords = vec![0; positions.len()];
+
+            for rotation in &rotations {
+                let (distance, right) = match rotation {
+                    Rotation::Left(distance) => (*distance, false),
+                    Rotation::Right(distance) => (*distance, true),
+                };
+
+                for _ in 0..distance {
+                    for dial in 0..positions.len() {
+                        let wrapped = if right {
+                            positions[dial] = (positions[dial] + 1) % dial_size;
+                            positions[dial] == 0
+                        } else {
+                            positions[dial] = (positions[dial] + dial_size - 1) % dial_size;
+                            positions[dial] == dial_size - 1
+                        };
+
+                        if positions[dial] == 0 {
+                            passes_passwords[dial] += 1;
+                        }
+
+                        if !wrapped {
+                            break;
+                        }
+                    }
+                }
+
+                for dial in 0..positions.len() {
+                    if positions[dial] == 0 {
+                        stops_passwords[dial] += 1;
+                    }
+                }
+            }
+
+            let safe = Safe {
+                rotations,
+                dial_size,
+                initial_positions,
+            };
+
+            assert_eq!(stops_passwords, safe.stops_passwords());
+            assert_eq!(passes_passwords, safe.passes_passwords());
+        }
+    }
+
+    #[test]
+    fn test_safe_with_dials() {
+        let safe = Safe::try_from(Cursor::new("R3\nL5\nR12\nL10\n".as_bytes()))
+            .unwrap()
+            .with_dials(10, &[2])
+            .unwrap();
+
+        // 2 -> 5 -> 0 -> 2 -> 2
+        assert_eq!(1, safe.stops_password());
+        assert_eq!(3, safe.passes_password());
+
+        assert!(
+            Safe::try_from(Cursor::new(TEST_SAFE.as_bytes()))
+                .unwrap()
+                .with_dials(0, &[0])
+                .is_err()
+        );
+        assert!(
+            Safe::try_from(Cursor::new(TEST_SAFE.as_bytes()))
+                .unwrap()
+                .with_dials(10, &[10])
+                .is_err()
+        );
+    }
 }
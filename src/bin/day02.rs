@@ -1,29 +1,44 @@
+use advent_of_code_2025::intervals;
 use std::env;
 use std::error::Error;
 use std::fs::File;
 use std::io::Read;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args: Vec<String> = env::args().collect();
 
-    if let Some(path) = args.get(1) {
+    let merge_ranges = args.iter().skip(1).any(|arg| arg == "--merge-ranges");
+
+    if let Some(path) = args.iter().skip(1).find(|arg| !arg.starts_with("--")) {
         let mut ranges = String::new();
         File::open(path)?.read_to_string(&mut ranges)?;
 
-        println!("Sum of invalid IDs: {}", invalid_id_sum(&ranges));
+        println!(
+            "Sum of invalid IDs: {}",
+            invalid_id_sum(&ranges, merge_ranges)
+        );
 
         Ok(())
     } else {
-        Err("Usage: day02 INPUT_FILE_PATH".into())
+        Err("Usage: day02 [--merge-ranges] INPUT_FILE_PATH".into())
     }
 }
 
-fn invalid_id_sum(ranges: &str) -> u64 {
-    ranges.split(',')
-        .filter_map(|s| IdRange::from_str(s).ok())
-        .flat_map(|id_range| id_range.invalid_ids())
-        .sum()
+fn invalid_id_sum(ranges: &str, merge_ranges: bool) -> u64 {
+    let id_ranges = ranges.split(',').filter_map(|s| IdRange::from_str(s).ok());
+
+    if merge_ranges {
+        // Overlapping ranges would otherwise count the invalid IDs they share more than once
+        intervals::merge_overlapping(id_ranges.map(RangeInclusive::from))
+            .into_iter()
+            .map(IdRange::from)
+            .flat_map(|id_range| id_range.invalid_ids())
+            .sum()
+    } else {
+        id_ranges.flat_map(|id_range| id_range.invalid_ids()).sum()
+    }
 }
 
 struct IdRange {
@@ -66,6 +81,21 @@ impl IdRange {
     }
 }
 
+impl From<RangeInclusive<u64>> for IdRange {
+    fn from(range: RangeInclusive<u64>) -> Self {
+        IdRange {
+            start: *range.start(),
+            end: *range.end(),
+        }
+    }
+}
+
+impl From<IdRange> for RangeInclusive<u64> {
+    fn from(id_range: IdRange) -> Self {
+        RangeInclusive::new(id_range.start, id_range.end)
+    }
+}
+
 impl FromStr for IdRange {
     type Err = Box<dyn Error>;
 
@@ -105,6 +135,27 @@ mod test {
 
     #[test]
     fn test_invalid_id_sum() {
-        assert_eq!(1227775554, invalid_id_sum("11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124"));
+        assert_eq!(
+            1227775554,
+            invalid_id_sum(
+                "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124",
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_id_sum_merge_ranges() {
+        assert_eq!(88, invalid_id_sum("11-22,20-33", false));
+        assert_eq!(66, invalid_id_sum("11-33", false));
+        assert_eq!(66, invalid_id_sum("11-22,20-33", true));
+        assert_eq!(33, invalid_id_sum("11-22,11-22", true));
+        assert_eq!(
+            1227775554,
+            invalid_id_sum(
+                "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124,15-22",
+                true
+            )
+        );
     }
 }
@@ -1,51 +1,376 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::env;
 use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::ops::AddAssign;
 use std::str::FromStr;
 
+const USAGE: &str = "Usage: day03 [--minimize] [--max-gap GAP] [--min-digit-count DIGIT COUNT] [--short-banks fail|skip|zero] [--active-batteries COUNT[,COUNT...]|all] [--radix RADIX] [--show] [--table] INPUT_FILE_PATH";
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
+    let mut args = env::args().skip(1);
 
-    if let Some(path) = args.get(1) {
-        println!(
-            "Joltage sum with 2 active batteries: {}",
-            joltage_sum(BufReader::new(File::open(path)?), 2)?
-        );
+    let mut path = None;
+    let mut minimize = false;
+    let mut max_gap = None;
+    let mut min_digit_count = None;
+    let mut show = false;
+    let mut short_banks = ShortBanks::Fail;
+    let mut active_battery_counts = ActiveBatteryCounts::Counts(vec![2, 12]);
+    let mut table = false;
+    let mut radix = 10;
 
-        println!(
-            "Joltage sum with 12 active batteries: {}",
-            joltage_sum(BufReader::new(File::open(path)?), 12)?
-        );
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--minimize" => minimize = true,
+            "--show" => show = true,
+            "--table" => table = true,
+            "--radix" => radix = args.next().ok_or(USAGE)?.parse()?,
+            "--active-batteries" => active_battery_counts = args.next().ok_or(USAGE)?.parse()?,
+            "--short-banks" => short_banks = args.next().ok_or(USAGE)?.parse()?,
+            "--max-gap" => max_gap = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--min-digit-count" => {
+                // We don't know the radix yet, so hang on to the digit as a string for now
+                min_digit_count = Some((
+                    args.next().ok_or(USAGE)?,
+                    args.next().ok_or(USAGE)?.parse()?,
+                ))
+            }
+            _ => path = Some(arg),
+        }
+    }
+
+    // Check the radix up front, since parsing the digit for --min-digit-count and printing the
+    // sums would otherwise panic on a bad one, even if there aren't any battery banks
+    if !(2..=36).contains(&radix) {
+        return Err("Radix must be between 2 and 36".into());
+    }
+
+    let mut rule = if minimize {
+        SelectionRule::minimize()
+    } else {
+        SelectionRule::maximize()
+    };
+
+    if let Some(max_gap) = max_gap {
+        rule = rule.with_max_gap(max_gap);
+    }
+
+    if let Some((digit, count)) = min_digit_count {
+        rule = rule.with_min_digit_count(u32::from_str_radix(&digit, radix)?, count);
+    }
+
+    if let Some(path) = path {
+        let battery_banks = parse_battery_banks(BufReader::new(File::open(&path)?), radix)?;
+
+        if show {
+            show_active_batteries(&battery_banks, &active_battery_counts, &rule, short_banks)
+                .map_err(|error| error.to_string())?;
+            println!();
+        }
+
+        let joltage_sums = joltage_sums(&battery_banks, &active_battery_counts, &rule, short_banks)
+            .map_err(|error| error.to_string())?;
+
+        if table {
+            println!("{:>16} {:>24}", "Active batteries", "Joltage sum");
+
+            for (active_batteries, joltage_sum) in joltage_sums {
+                println!(
+                    "{:>16} {:>24}",
+                    active_batteries,
+                    joltage_sum.to_string_radix(radix)
+                );
+            }
+        } else {
+            for (active_batteries, joltage_sum) in joltage_sums {
+                println!(
+                    "Joltage sum with {} active batteries: {}",
+                    active_batteries,
+                    joltage_sum.to_string_radix(radix)
+                );
+            }
+        }
 
         Ok(())
     } else {
-        Err("Usage: day03 INPUT_FILE_PATH".into())
+        Err(USAGE.into())
     }
 }
 
-fn joltage_sum(reader: impl BufRead, active_batteries: usize) -> Result<u64, Box<dyn Error>> {
+fn parse_battery_banks(
+    reader: impl BufRead,
+    radix: u32,
+) -> Result<Vec<BatteryBank>, Box<dyn Error>> {
     reader
         .lines()
-        .map_while(|result| result.ok())
-        .map(|line| BatteryBank::from_str(&line))
-        .map(|battery_bank| battery_bank.map(|b| b.max_joltage(active_batteries)))
-        .sum::<Result<_, _>>()
+        .enumerate()
+        .map(|(index, line)| {
+            BatteryBank::from_str_radix(&line?, radix)
+                .map_err(|error| format!("Line {}: {}", index + 1, error).into())
+        })
+        .collect()
+}
+
+// Returns the sum of the joltages of all battery banks for each requested number of active
+// batteries, sorted by the number of active batteries
+fn joltage_sums(
+    battery_banks: &[BatteryBank],
+    active_battery_counts: &ActiveBatteryCounts,
+    rule: &SelectionRule,
+    short_banks: ShortBanks,
+) -> Result<Vec<(usize, Joltage)>, Box<dyn Error>> {
+    let mut joltage_sums = BTreeMap::new();
+
+    if let ActiveBatteryCounts::Counts(counts) = active_battery_counts {
+        // Make sure we report every requested count, even if everything gets skipped
+        for active_batteries in counts {
+            joltage_sums.insert(*active_batteries, Joltage::default());
+        }
+    }
+
+    for (index, battery_bank) in battery_banks.iter().enumerate() {
+        for active_batteries in active_battery_counts.for_bank(battery_bank) {
+            if let Err(error) = battery_bank.check_length(active_batteries) {
+                match short_banks {
+                    ShortBanks::Fail => return Err(error.on_line(index + 1).into()),
+                    // Skipped and zero-scored banks both contribute nothing to the sum
+                    ShortBanks::Skip | ShortBanks::Zero => continue,
+                }
+            }
+
+            *joltage_sums.entry(active_batteries).or_default() += &battery_bank
+                .joltage(active_batteries, rule)
+                .ok_or_else(|| {
+                    format!(
+                        "Line {}: No selection of batteries satisfies the rule",
+                        index + 1
+                    )
+                })?;
+        }
+    }
+
+    Ok(joltage_sums.into_iter().collect())
+}
+
+fn show_active_batteries(
+    battery_banks: &[BatteryBank],
+    active_battery_counts: &ActiveBatteryCounts,
+    rule: &SelectionRule,
+    short_banks: ShortBanks,
+) -> Result<(), Box<dyn Error>> {
+    for (index, battery_bank) in battery_banks.iter().enumerate() {
+        for active_batteries in active_battery_counts.for_bank(battery_bank) {
+            if let Err(error) = battery_bank.check_length(active_batteries) {
+                match short_banks {
+                    ShortBanks::Fail => return Err(error.on_line(index + 1).into()),
+                    ShortBanks::Skip => continue,
+                    ShortBanks::Zero => {
+                        println!("{:>3}: {} 0", active_batteries, battery_bank.highlight(&[]));
+                        continue;
+                    }
+                }
+            }
+
+            match battery_bank.active_batteries(active_batteries, rule) {
+                Some(indices) => println!(
+                    "{:>3}: {} {}",
+                    active_batteries,
+                    battery_bank.highlight(&indices),
+                    battery_bank
+                        .joltage_from(&indices)
+                        .to_string_radix(battery_bank.radix)
+                ),
+                None => println!(
+                    "{:>3}: {} (no valid selection)",
+                    active_batteries,
+                    battery_bank.highlight(&[])
+                ),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum ActiveBatteryCounts {
+    // Every count from 1 to the length of each individual bank
+    All,
+    Counts(Vec<usize>),
+}
+
+impl ActiveBatteryCounts {
+    pub fn for_bank(&self, battery_bank: &BatteryBank) -> Vec<usize> {
+        match self {
+            ActiveBatteryCounts::All => (1..=battery_bank.batteries.len()).collect(),
+            ActiveBatteryCounts::Counts(counts) => counts.clone(),
+        }
+    }
+}
+
+impl FromStr for ActiveBatteryCounts {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "all" {
+            Ok(ActiveBatteryCounts::All)
+        } else {
+            let mut counts: Vec<usize> = s
+                .split(',')
+                .map(|count| count.parse())
+                .collect::<Result<_, _>>()?;
+
+            // Each count gets one sum, so a repeated count would otherwise be added in twice
+            counts.sort_unstable();
+            counts.dedup();
+
+            Ok(ActiveBatteryCounts::Counts(counts))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ShortBanks {
+    Fail,
+    Skip,
+    Zero,
 }
 
+impl FromStr for ShortBanks {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fail" => Ok(ShortBanks::Fail),
+            "skip" => Ok(ShortBanks::Skip),
+            "zero" => Ok(ShortBanks::Zero),
+            _ => Err("Short bank handling must be one of \"fail\", \"skip\", or \"zero\"".into()),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct ShortBankError {
+    // The line of the input that held the bank, if we know it
+    line: Option<usize>,
+    batteries: usize,
+    active_batteries: usize,
+}
+
+impl ShortBankError {
+    pub fn on_line(self, line: usize) -> Self {
+        ShortBankError {
+            line: Some(line),
+            ..self
+        }
+    }
+}
+
+impl Display for ShortBankError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "Line {}: ", line)?;
+        }
+
+        write!(
+            f,
+            "battery bank has {} batteries, but {} must be active",
+            self.batteries, self.active_batteries
+        )
+    }
+}
+
+impl Error for ShortBankError {}
+
+// Use windowed selection for fewer active batteries than this, and the monotonic stack otherwise
+const WINDOWED_SELECTION_CUTOFF: usize = 16;
+
 struct BatteryBank {
     batteries: Vec<u32>,
+    radix: u32,
 }
 
 impl BatteryBank {
-    pub fn max_joltage(&self, active_batteries: usize) -> u64 {
-        let mut joltage = 0u64;
-        let mut left = 0;
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, Box<dyn Error>> {
+        if !(2..=36).contains(&radix) {
+            return Err("Radix must be between 2 and 36".into());
+        }
+
+        let batteries: Vec<u32> = s
+            .chars()
+            .map(|c| c.to_digit(radix).ok_or("Could not parse digit"))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        assert!(active_batteries <= self.batteries.len());
+        Ok(BatteryBank { batteries, radix })
+    }
+
+    pub fn check_length(&self, active_batteries: usize) -> Result<(), ShortBankError> {
+        if self.batteries.len() < active_batteries {
+            Err(ShortBankError {
+                line: None,
+                batteries: self.batteries.len(),
+                active_batteries,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn max_joltage(&self, active_batteries: usize) -> Result<Joltage, ShortBankError> {
+        Ok(self.joltage_from(&self.max_joltage_batteries(active_batteries)?))
+    }
+
+    pub fn max_joltage_batteries(
+        &self,
+        active_batteries: usize,
+    ) -> Result<Vec<usize>, ShortBankError> {
+        self.check_length(active_batteries)?;
+
+        // Rescanning a window for each pick costs O(n * k), but those scans are so cheap that
+        // they beat the linear-time stack until k gets fairly large
+        if active_batteries < WINDOWED_SELECTION_CUTOFF {
+            Ok(self.windowed_max_joltage_batteries(active_batteries))
+        } else {
+            Ok(self.stack_max_joltage_batteries(active_batteries))
+        }
+    }
+
+    fn stack_max_joltage_batteries(&self, active_batteries: usize) -> Vec<usize> {
+        // Keep a stack of candidate batteries whose joltages never increase from bottom to top.
+        // Whenever a better battery comes along, anything smaller on top of the stack would do
+        // better to step aside (as long as we can still afford to leave batteries out).
+        let mut droppable_batteries = self.batteries.len() - active_batteries;
+        let mut indices: Vec<usize> = Vec::with_capacity(self.batteries.len());
+
+        for (i, joltage) in self.batteries.iter().enumerate() {
+            while droppable_batteries > 0
+                && indices
+                    .last()
+                    .is_some_and(|&top| self.batteries[top] < *joltage)
+            {
+                indices.pop();
+                droppable_batteries -= 1;
+            }
+
+            indices.push(i);
+        }
+
+        // If we never ran out of batteries to drop, the leftovers are at the end
+        indices.truncate(active_batteries);
+
+        indices
+    }
+
+    fn windowed_max_joltage_batteries(&self, active_batteries: usize) -> Vec<usize> {
+        let mut indices = Vec::with_capacity(active_batteries);
+        let mut left = 0;
 
         for reserved_batteries in (0..active_batteries).rev() {
-            let (position, best_joltage) = self.batteries[left..self.batteries.len() - reserved_batteries]
+            let (position, _) = self.batteries[left..self.batteries.len() - reserved_batteries]
                 .iter()
                 .enumerate()
                 // This may seem a little funky, but max_by_key will return the LAST element it
@@ -54,33 +379,422 @@ impl BatteryBank {
                 .max_by_key(|(_, j)| *j)
                 .expect("Non-empty bank of batteries must have at least one max value");
 
+            indices.push(left + position);
             left += position + 1;
+        }
+
+        indices
+    }
+
+    pub fn joltage(&self, active_batteries: usize, rule: &SelectionRule) -> Option<Joltage> {
+        if self.can_use_greedy_selection(active_batteries, rule) {
+            return self.max_joltage(active_batteries).ok();
+        }
+
+        self.select(active_batteries, rule)
+            .map(|indices| self.joltage_from(&indices))
+    }
 
-            joltage = (joltage * 10) + (*best_joltage as u64);
+    pub fn active_batteries(
+        &self,
+        active_batteries: usize,
+        rule: &SelectionRule,
+    ) -> Option<Vec<usize>> {
+        if self.can_use_greedy_selection(active_batteries, rule) {
+            return self.max_joltage_batteries(active_batteries).ok();
+        }
+
+        self.select(active_batteries, rule)
+    }
+
+    fn can_use_greedy_selection(&self, active_batteries: usize, rule: &SelectionRule) -> bool {
+        // The greedy approach is much faster than the general solution, and works just fine when
+        // there are no extra constraints
+        *rule == SelectionRule::maximize() && active_batteries <= self.batteries.len()
+    }
+
+    pub fn highlight(&self, indices: &[usize]) -> String {
+        let mut highlighted = String::new();
+
+        // Checking `indices` for every battery would make long banks with many active batteries
+        // quadratic, so mark the active positions up front
+        let mut active = vec![false; self.batteries.len()];
+
+        for &i in indices {
+            active[i] = true;
+        }
+
+        for (i, battery) in self.batteries.iter().enumerate() {
+            let digit = char::from_digit(*battery, self.radix)
+                .expect("Batteries must be single digits in the bank's radix");
+
+            if active[i] {
+                // Bold and inverted, so active batteries stand out even without color support
+                highlighted.push_str(&format!("\x1b[1;7m{}\x1b[0m", digit));
+            } else {
+                highlighted.push(digit);
+            }
+        }
+
+        highlighted
+    }
+
+    fn joltage_from(&self, indices: &[usize]) -> Joltage {
+        Joltage::from_digits(indices.iter().map(|&i| self.batteries[i]), self.radix)
+    }
+
+    // Returns the indices of the batteries that produce the best joltage under the given rule, or
+    // `None` if no selection of batteries satisfies the rule
+    fn select(&self, active_batteries: usize, rule: &SelectionRule) -> Option<Vec<usize>> {
+        let len = self.batteries.len();
+
+        let (required_digit, required_count) = rule.min_digit_count.unwrap_or((0, 0));
+        let is_required = |i: usize| usize::from(self.batteries[i] == required_digit);
+
+        // We can't pick more batteries with the required digit than we pick overall, and bailing
+        // out here keeps impossible counts from sizing the tables below
+        if required_count > active_batteries {
+            return None;
+        }
+
+        if active_batteries == 0 {
+            return Some(Vec::new());
+        }
+
+        if active_batteries > len {
+            return None;
+        }
+
+        // The range of positions we can pick next if the last battery we picked was at `i`
+        let next_positions = |i: usize| {
+            let max = rule
+                .max_gap
+                .map(|max_gap| i.saturating_add(max_gap).saturating_add(1).min(len - 1))
+                .unwrap_or(len - 1);
+
+            i + 1..=max
+        };
+
+        // `feasible[cell(r, i, need)]` answers the question "if we just picked the battery at
+        // position `i`, can we pick `r` more batteries and wind up with at least `need` batteries
+        // with the required digit?"
+        let needs = required_count + 1;
+        let cell = |r: usize, i: usize, need: usize| (((r * len) + i) * needs) + need;
+        let mut feasible = vec![false; active_batteries * len * needs];
+
+        for i in 0..len {
+            feasible[cell(0, i, 0)] = true;
+        }
+
+        for r in 1..active_batteries {
+            for i in 0..len {
+                for need in 0..needs {
+                    feasible[cell(r, i, need)] = next_positions(i)
+                        .any(|j| feasible[cell(r - 1, j, need.saturating_sub(is_required(j)))]);
+                }
+            }
+        }
+
+        // Build the best sequence one digit at a time. Several positions may tie for the best
+        // digit at each step, and (because of the gap rule) it's not safe to just take the first
+        // one, so we carry every tied state forward and remember how we got to each.
+        let mut parents: Vec<Vec<Option<usize>>> = Vec::with_capacity(active_batteries);
+        let mut states: Vec<(usize, usize)> = Vec::new();
+
+        for step in 0..active_batteries {
+            let remaining = active_batteries - step - 1;
+
+            let candidates: Vec<(usize, usize, Option<usize>)> = if step == 0 {
+                (0..len)
+                    .map(|j| (j, required_count.saturating_sub(is_required(j)), None))
+                    .filter(|(j, need, _)| feasible[cell(remaining, *j, *need)])
+                    .collect()
+            } else {
+                states
+                    .iter()
+                    .flat_map(|&(i, need)| {
+                        next_positions(i)
+                            .map(move |j| (j, need.saturating_sub(is_required(j)), Some(i)))
+                    })
+                    .filter(|(j, need, _)| feasible[cell(remaining, *j, *need)])
+                    .collect()
+            };
+
+            let best = candidates
+                .iter()
+                .map(|(j, _, _)| self.batteries[*j])
+                .reduce(|a, b| match rule.objective {
+                    Objective::Maximize => a.max(b),
+                    Objective::Minimize => a.min(b),
+                })?;
+
+            let mut step_parents = vec![None; len];
+            let mut visited = vec![false; len * needs];
+
+            states.clear();
+
+            for (j, need, parent) in candidates {
+                if self.batteries[j] == best && !visited[(j * needs) + need] {
+                    visited[(j * needs) + need] = true;
+                    states.push((j, need));
+
+                    if step_parents[j].is_none() {
+                        step_parents[j] = parent;
+                    }
+                }
+            }
+
+            parents.push(step_parents);
+        }
+
+        // Any surviving state is an optimal finish; walk back up through the parents to recover the
+        // positions we picked along the way
+        let (mut position, _) = *states.first()?;
+        let mut indices = vec![position];
+
+        for step_parents in parents.iter().skip(1).rev() {
+            position = step_parents[position].expect("Every state after the first has a parent");
+            indices.push(position);
+        }
+
+        indices.reverse();
+
+        Some(indices)
+    }
+}
+
+// An arbitrarily large, non-negative joltage; with more than 19 or so active batteries, joltages
+// won't fit in a u64. Digits stay in the radix of the bank they came from, so building, summing,
+// and printing a joltage in that radix are all linear in its length.
+#[derive(Clone, Debug)]
+struct Joltage {
+    // Little-endian digits with no trailing zeros, so zero has no digits at all
+    digits: Vec<u32>,
+    radix: u32,
+}
+
+impl Joltage {
+    pub fn from_digits(digits: impl IntoIterator<Item = u32>, radix: u32) -> Self {
+        let mut digits: Vec<u32> = digits.into_iter().collect();
+        digits.reverse();
+
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        Joltage { digits, radix }
+    }
+
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        assert!((2..=36).contains(&radix));
+
+        let joltage = self.to_radix(radix);
+
+        if joltage.digits.is_empty() {
+            return String::from("0");
         }
 
         joltage
+            .digits
+            .iter()
+            .rev()
+            .map(|&digit| char::from_digit(digit, radix).unwrap())
+            .collect()
+    }
+
+    // Converting between radixes is quadratic, but every joltage in a run shares its banks' radix,
+    // so this only does real work when printing a non-decimal joltage in decimal
+    fn to_radix(&self, radix: u32) -> Joltage {
+        if radix == self.radix {
+            return self.clone();
+        }
+
+        // Most significant digit first, for long division
+        let mut remaining: Vec<u32> = self.digits.iter().rev().copied().collect();
+        let mut digits = Vec::new();
+
+        while !remaining.is_empty() {
+            let mut remainder = 0u64;
+
+            for digit in remaining.iter_mut() {
+                let value = (remainder * self.radix as u64) + *digit as u64;
+
+                *digit = (value / radix as u64) as u32;
+                remainder = value % radix as u64;
+            }
+
+            let leading_zeros = remaining.iter().take_while(|&&digit| digit == 0).count();
+            remaining.drain(..leading_zeros);
+
+            digits.push(remainder as u32);
+        }
+
+        Joltage { digits, radix }
+    }
+}
+
+impl Default for Joltage {
+    fn default() -> Self {
+        Joltage {
+            digits: Vec::new(),
+            radix: 10,
+        }
+    }
+}
+
+impl From<u64> for Joltage {
+    fn from(mut value: u64) -> Self {
+        let mut digits = Vec::new();
+
+        while value > 0 {
+            digits.push((value % 10) as u32);
+            value /= 10;
+        }
+
+        Joltage { digits, radix: 10 }
+    }
+}
+
+impl PartialEq for Joltage {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Joltage {}
+
+impl PartialEq<u64> for Joltage {
+    fn eq(&self, other: &u64) -> bool {
+        self.digits.iter().rev().try_fold(0u64, |value, &digit| {
+            value
+                .checked_mul(self.radix as u64)?
+                .checked_add(digit as u64)
+        }) == Some(*other)
+    }
+}
+
+impl PartialEq<Joltage> for u64 {
+    fn eq(&self, other: &Joltage) -> bool {
+        other == self
+    }
+}
+
+impl Ord for Joltage {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.radix != other.radix {
+            return self.cmp(&other.to_radix(self.radix));
+        }
+
+        self.digits
+            .len()
+            .cmp(&other.digits.len())
+            .then_with(|| self.digits.iter().rev().cmp(other.digits.iter().rev()))
+    }
+}
+
+impl PartialOrd for Joltage {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl AddAssign<&Joltage> for Joltage {
+    fn add_assign(&mut self, other: &Joltage) {
+        if self.digits.is_empty() {
+            // Zero has no meaningful radix, so take on the other joltage's
+            self.clone_from(other);
+            return;
+        }
+
+        if self.radix != other.radix {
+            *self += &other.to_radix(self.radix);
+            return;
+        }
+
+        if self.digits.len() < other.digits.len() {
+            self.digits.resize(other.digits.len(), 0);
+        }
+
+        let mut carry = 0;
+
+        for (i, digit) in self.digits.iter_mut().enumerate() {
+            let value = *digit + other.digits.get(i).unwrap_or(&0) + carry;
+
+            *digit = value % self.radix;
+            carry = value / self.radix;
+        }
+
+        if carry > 0 {
+            self.digits.push(carry);
+        }
     }
 }
 
+impl Display for Joltage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad(&self.to_string_radix(10))
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct SelectionRule {
+    objective: Objective,
+    max_gap: Option<usize>,
+    min_digit_count: Option<(u32, usize)>,
+}
+
+impl SelectionRule {
+    pub fn maximize() -> Self {
+        SelectionRule::default()
+    }
+
+    pub fn minimize() -> Self {
+        SelectionRule {
+            objective: Objective::Minimize,
+            ..SelectionRule::default()
+        }
+    }
+
+    pub fn with_max_gap(self, max_gap: usize) -> Self {
+        SelectionRule {
+            max_gap: Some(max_gap),
+            ..self
+        }
+    }
+
+    pub fn with_min_digit_count(self, digit: u32, count: usize) -> Self {
+        SelectionRule {
+            min_digit_count: Some((digit, count)),
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum Objective {
+    #[default]
+    Maximize,
+    Minimize,
+}
+
 impl FromStr for BatteryBank {
     type Err = Box<dyn Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let batteries: Vec<u32> = s
-            .chars()
-            .map(|c| c.to_digit(10).ok_or("Could not parse digit"))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(BatteryBank { batteries })
+        BatteryBank::from_str_radix(s, 10)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{BatteryBank, joltage_sum};
+    use crate::{
+        ActiveBatteryCounts, BatteryBank, Joltage, SelectionRule, ShortBankError, ShortBanks,
+        joltage_sums, parse_battery_banks,
+    };
+    use advent_of_code_2025::random;
     use indoc::indoc;
-    use std::io::Cursor;
+    use std::error::Error;
+    use std::io::{BufRead, Cursor};
     use std::str::FromStr;
 
     const TEST_BATTERY_BANKS: &str = indoc! {"
@@ -97,6 +811,7 @@ mod test {
             BatteryBank::from_str("987654321111111")
                 .unwrap()
                 .max_joltage(2)
+                .unwrap()
         );
 
         assert_eq!(
@@ -104,6 +819,7 @@ mod test {
             BatteryBank::from_str("811111111111119")
                 .unwrap()
                 .max_joltage(2)
+                .unwrap()
         );
 
         assert_eq!(
@@ -111,6 +827,7 @@ mod test {
             BatteryBank::from_str("234234234234278")
                 .unwrap()
                 .max_joltage(2)
+                .unwrap()
         );
 
         assert_eq!(
@@ -118,6 +835,7 @@ mod test {
             BatteryBank::from_str("818181911112111")
                 .unwrap()
                 .max_joltage(2)
+                .unwrap()
         );
 
         assert_eq!(
@@ -125,6 +843,7 @@ mod test {
             BatteryBank::from_str("987654321111111")
                 .unwrap()
                 .max_joltage(12)
+                .unwrap()
         );
 
         assert_eq!(
@@ -132,6 +851,7 @@ mod test {
             BatteryBank::from_str("811111111111119")
                 .unwrap()
                 .max_joltage(12)
+                .unwrap()
         );
 
         assert_eq!(
@@ -139,6 +859,7 @@ mod test {
             BatteryBank::from_str("234234234234278")
                 .unwrap()
                 .max_joltage(12)
+                .unwrap()
         );
 
         assert_eq!(
@@ -146,19 +867,555 @@ mod test {
             BatteryBank::from_str("818181911112111")
                 .unwrap()
                 .max_joltage(12)
+                .unwrap()
         );
     }
 
+    fn joltage_sum(
+        reader: impl BufRead,
+        active_batteries: usize,
+        rule: &SelectionRule,
+        short_banks: ShortBanks,
+    ) -> Result<Joltage, Box<dyn Error>> {
+        let joltage_sums = joltage_sums(
+            &parse_battery_banks(reader, 10)?,
+            &ActiveBatteryCounts::Counts(vec![active_batteries]),
+            rule,
+            short_banks,
+        )?;
+
+        assert_eq!(1, joltage_sums.len());
+        assert_eq!(active_batteries, joltage_sums[0].0);
+
+        Ok(joltage_sums[0].1.clone())
+    }
+
     #[test]
     fn test_max_joltage_sum() {
         assert_eq!(
             357,
-            joltage_sum(Cursor::new(TEST_BATTERY_BANKS), 2).unwrap()
+            joltage_sum(
+                Cursor::new(TEST_BATTERY_BANKS),
+                2,
+                &SelectionRule::maximize(),
+                ShortBanks::Fail
+            )
+            .unwrap()
         );
 
         assert_eq!(
             3121910778619,
-            joltage_sum(Cursor::new(TEST_BATTERY_BANKS), 12).unwrap()
+            joltage_sum(
+                Cursor::new(TEST_BATTERY_BANKS),
+                12,
+                &SelectionRule::maximize(),
+                ShortBanks::Fail
+            )
+            .unwrap()
+        );
+    }
+
+    // Small enough to check every possible selection of batteries by brute force
+    fn brute_force_joltage(
+        batteries: &[u32],
+        active_batteries: usize,
+        accept: impl Fn(&[usize]) -> bool,
+        minimize: bool,
+    ) -> Option<Joltage> {
+        (0u32..(1 << batteries.len()))
+            .filter(|mask| mask.count_ones() as usize == active_batteries)
+            .map(|mask| {
+                (0..batteries.len())
+                    .filter(|i| mask & (1 << i) != 0)
+                    .collect::<Vec<usize>>()
+            })
+            .filter(|indices| accept(indices))
+            .map(|indices| {
+                indices
+                    .iter()
+                    .fold(0, |joltage, &i| (joltage * 10) + batteries[i] as u64)
+            })
+            .reduce(|a, b| if minimize { a.min(b) } else { a.max(b) })
+            .map(Joltage::from)
+    }
+
+    fn random_banks() -> Vec<BatteryBank> {
+        let mut next_random = random::generator::<u32>(0x853c_49e6_748f_ea9b);
+
+        (0..300)
+            .map(|_| {
+                let len = 1 + next_random(12) as usize;
+
+                // Use a narrow range of digits in some banks so there are lots of ties
+                let digits = if next_random(2) == 0 { 3 } else { 10 };
+
+                BatteryBank {
+                    batteries: (0..len).map(|_| next_random(digits)).collect(),
+                    radix: 10,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_joltage_sums() {
+        assert_eq!(
+            vec![(2, Joltage::from(357)), (12, Joltage::from(3121910778619))],
+            joltage_sums(
+                &parse_battery_banks(Cursor::new(TEST_BATTERY_BANKS), 10).unwrap(),
+                &ActiveBatteryCounts::Counts(vec![12, 2]),
+                &SelectionRule::maximize(),
+                ShortBanks::Fail
+            )
+            .unwrap()
+        );
+
+        // Repeating a count mustn't add its sum in twice
+        assert_eq!(
+            vec![(2, Joltage::from(357))],
+            joltage_sums(
+                &parse_battery_banks(Cursor::new(TEST_BATTERY_BANKS), 10).unwrap(),
+                &"2,2".parse().unwrap(),
+                &SelectionRule::maximize(),
+                ShortBanks::Fail
+            )
+            .unwrap()
+        );
+
+        let joltage_sums = joltage_sums(
+            &parse_battery_banks(Cursor::new("9182\n5\n"), 10).unwrap(),
+            &ActiveBatteryCounts::All,
+            &SelectionRule::maximize(),
+            ShortBanks::Fail,
+        )
+        .unwrap();
+
+        // Banks only count toward the numbers of active batteries they can actually support
+        assert_eq!(
+            vec![
+                (1, Joltage::from(9 + 5)),
+                (2, Joltage::from(98)),
+                (3, Joltage::from(982)),
+                (4, Joltage::from(9182))
+            ],
+            joltage_sums
+        );
+
+        assert_eq!(
+            ActiveBatteryCounts::Counts(vec![2, 12]),
+            "2,12".parse().unwrap()
+        );
+        assert_eq!(
+            ActiveBatteryCounts::Counts(vec![2, 12]),
+            "12,2,2".parse().unwrap()
+        );
+        assert_eq!(ActiveBatteryCounts::All, "all".parse().unwrap());
+        assert!("2,twelve".parse::<ActiveBatteryCounts>().is_err());
+    }
+
+    #[test]
+    fn test_joltage_sum_short_banks() {
+        let battery_banks = indoc! {"
+            987654321111111
+            811111111111119
+            1234
+            234234234234278
+            818181911112111
+        "};
+
+        let error = joltage_sum(
+            Cursor::new(battery_banks),
+            12,
+            &SelectionRule::maximize(),
+            ShortBanks::Fail,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            Some(&ShortBankError {
+                line: Some(3),
+                batteries: 4,
+                active_batteries: 12,
+            }),
+            error.downcast_ref::<ShortBankError>()
+        );
+
+        assert_eq!(
+            "Line 3: battery bank has 4 batteries, but 12 must be active",
+            error.to_string()
+        );
+
+        for short_banks in [ShortBanks::Skip, ShortBanks::Zero] {
+            assert_eq!(
+                3121910778619,
+                joltage_sum(
+                    Cursor::new(battery_banks),
+                    12,
+                    &SelectionRule::maximize(),
+                    short_banks
+                )
+                .unwrap()
+            );
+        }
+
+        // Short banks are only a problem if they're too short
+        assert_eq!(
+            357 + 34,
+            joltage_sum(
+                Cursor::new(battery_banks),
+                2,
+                &SelectionRule::maximize(),
+                ShortBanks::Fail
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_max_joltage_batteries() {
+        let bank = BatteryBank::from_str("818181911112111").unwrap();
+
+        assert_eq!(vec![6, 11], bank.max_joltage_batteries(2).unwrap());
+        assert_eq!(
+            vec![0, 2, 4, 6, 7, 8, 9, 10, 11, 12, 13, 14],
+            bank.max_joltage_batteries(12).unwrap()
+        );
+
+        // Ties go to the leftmost battery, leaving as many options as possible for later picks
+        assert_eq!(
+            vec![0, 1],
+            BatteryBank::from_str("9999")
+                .unwrap()
+                .max_joltage_batteries(2)
+                .unwrap()
+        );
+
+        assert_eq!(
+            Some(vec![6, 7]),
+            bank.active_batteries(2, &SelectionRule::maximize().with_max_gap(0))
+        );
+    }
+
+    #[test]
+    fn test_max_joltage_batteries_linear() {
+        for bank in random_banks() {
+            for active_batteries in 0..=bank.batteries.len() {
+                assert_eq!(
+                    bank.windowed_max_joltage_batteries(active_batteries),
+                    bank.stack_max_joltage_batteries(active_batteries)
+                );
+            }
+        }
+
+        // Long runs of equal batteries are where the two approaches are most likely to disagree
+        // about which of several identical batteries to use
+        let bank = BatteryBank {
+            batteries: [vec![5; 40], vec![9; 3], vec![5; 40], vec![1; 7]].concat(),
+            radix: 10,
+        };
+
+        for active_batteries in 0..=bank.batteries.len() {
+            assert_eq!(
+                bank.windowed_max_joltage_batteries(active_batteries),
+                bank.stack_max_joltage_batteries(active_batteries)
+            );
+        }
+    }
+
+    // Run with `cargo test --release --bin day03 -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_max_joltage_batteries() {
+        use std::time::Instant;
+
+        let mut next_random = random::generator::<u32>(0x2f69_8a8f_c1d7_e3b5);
+
+        let bank = BatteryBank {
+            batteries: (0..1_000_000).map(|_| next_random(10)).collect(),
+            radix: 10,
+        };
+
+        for active_batteries in [2, 12, 16, 24, 32, 48, 64, 100, 1_000] {
+            let start = Instant::now();
+            let windowed = bank.windowed_max_joltage_batteries(active_batteries);
+            let windowed_time = start.elapsed();
+
+            let start = Instant::now();
+            let stack = bank.stack_max_joltage_batteries(active_batteries);
+            let stack_time = start.elapsed();
+
+            let start = Instant::now();
+            let selected = bank.max_joltage_batteries(active_batteries).unwrap();
+            let selected_time = start.elapsed();
+
+            assert_eq!(windowed, stack);
+            assert_eq!(windowed, selected);
+
+            println!(
+                "k = {:>6}: windowed {:>12?}, stack {:>12?}, selected {:>12?}",
+                active_batteries, windowed_time, stack_time, selected_time
+            );
+        }
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            "8\x1b[1;7m1\x1b[0m8\x1b[1;7m9\x1b[0m",
+            BatteryBank::from_str("8189").unwrap().highlight(&[1, 3])
+        );
+    }
+
+    #[test]
+    fn test_radix_battery_banks() {
+        let bank = BatteryBank::from_str_radix("1f3a", 16).unwrap();
+
+        assert_eq!(0xfa, bank.max_joltage(2).unwrap());
+        assert_eq!(0xf3a, bank.max_joltage(3).unwrap());
+        assert_eq!(
+            Some(Joltage::from(0x13)),
+            bank.joltage(2, &SelectionRule::minimize())
+        );
+        assert_eq!("fa", bank.max_joltage(2).unwrap().to_string_radix(16));
+
+        let bank = BatteryBank::from_str_radix("0110100", 2).unwrap();
+
+        assert_eq!(0b1110, bank.max_joltage(4).unwrap());
+
+        let bank = BatteryBank::from_str_radix("hELLOzWorld", 36).unwrap();
+
+        assert_eq!("zwrl", bank.max_joltage(4).unwrap().to_string_radix(36));
+        assert_eq!(
+            "8\x1b[1;7mz\x1b[0m",
+            BatteryBank::from_str_radix("8z", 36)
+                .unwrap()
+                .highlight(&[1])
         );
+
+        assert!(BatteryBank::from_str_radix("1f3a", 10).is_err());
+        assert!(BatteryBank::from_str_radix("102", 2).is_err());
+        assert!(BatteryBank::from_str_radix("123", 37).is_err());
+        assert!(BatteryBank::from_str_radix("123", 1).is_err());
+
+        assert_eq!(
+            vec![(2, Joltage::from(0xfa + 0x9e))],
+            joltage_sums(
+                &parse_battery_banks(Cursor::new("1f3a\n9e\n"), 16).unwrap(),
+                &ActiveBatteryCounts::Counts(vec![2]),
+                &SelectionRule::maximize(),
+                ShortBanks::Fail
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_joltage_arithmetic() {
+        let big = "98765432109876543210987654321";
+
+        assert_eq!(
+            big,
+            Joltage::from_digits(big.chars().map(|c| c.to_digit(10).unwrap()), 10).to_string()
+        );
+
+        assert_eq!("0", Joltage::default().to_string());
+        assert_eq!("0", Joltage::from_digits([0, 0, 0], 10).to_string());
+        assert_eq!(u64::MAX, Joltage::from(u64::MAX));
+        assert_eq!("ff", Joltage::from(255).to_string_radix(16));
+
+        let mut sum = Joltage::from(u64::MAX);
+        sum += &Joltage::from(u64::MAX);
+        sum += &Joltage::from(2);
+
+        assert_eq!("36893488147419103232", sum.to_string());
+
+        let (small, medium, large) = (
+            Joltage::from(7),
+            Joltage::from(u32::MAX as u64),
+            Joltage::from(1 << 40),
+        );
+
+        assert!(small < medium);
+        assert!(medium < large);
+        assert!(large > small);
+        assert_ne!(Joltage::from(1 << 40), 0);
+        assert_eq!("   42", format!("{:>5}", Joltage::from(42)));
+
+        let mut hex = Joltage::default();
+        hex += &Joltage::from_digits([0xf, 0xa], 16);
+        hex += &Joltage::from_digits([0xf, 0xf], 16);
+        hex += &Joltage::from(6);
+
+        assert_eq!(Joltage::from(0xfa + 0xff + 6), hex);
+        assert_eq!("1ff", hex.to_string_radix(16));
+        assert_eq!("511", hex.to_string());
+        assert!(Joltage::from_digits([1, 0, 0], 16) > Joltage::from_digits([2, 5, 5], 10));
+    }
+
+    #[test]
+    fn test_max_joltage_short_bank() {
+        let bank = BatteryBank::from_str("9").unwrap();
+
+        assert_eq!(
+            Err(ShortBankError {
+                line: None,
+                batteries: 1,
+                active_batteries: 2,
+            }),
+            bank.max_joltage(2)
+        );
+
+        assert!(bank.max_joltage_batteries(2).is_err());
+
+        assert_eq!(
+            "battery bank has 1 batteries, but 2 must be active",
+            bank.max_joltage(2).unwrap_err().to_string()
+        );
+
+        assert_eq!(
+            "Line 4: battery bank has 1 batteries, but 2 must be active",
+            bank.max_joltage(2).unwrap_err().on_line(4).to_string()
+        );
+    }
+
+    #[test]
+    fn test_max_joltage_large() {
+        let bank = BatteryBank::from_str("9876543219876543219876543219876543219").unwrap();
+
+        assert_eq!(
+            "98769876543219876543219876543219",
+            bank.max_joltage(32).unwrap().to_string()
+        );
+
+        assert_eq!(
+            "9876543219876543219876543219876543219",
+            bank.max_joltage(37).unwrap().to_string()
+        );
+
+        let banks = "99999999999999999999999999\n11111111111111111111111111\n";
+
+        assert_eq!(
+            "111111111111111111111111110",
+            joltage_sum(
+                Cursor::new(banks),
+                26,
+                &SelectionRule::maximize(),
+                ShortBanks::Fail
+            )
+            .unwrap()
+            .to_string()
+        );
+
+        assert_eq!(
+            "11111111111111111111111110",
+            joltage_sum(
+                Cursor::new(banks),
+                25,
+                &SelectionRule::maximize().with_max_gap(1),
+                ShortBanks::Fail
+            )
+            .unwrap()
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_joltage() {
+        let bank = BatteryBank::from_str("818181911112111").unwrap();
+
+        assert_eq!(
+            Some(Joltage::from(92)),
+            bank.joltage(2, &SelectionRule::maximize())
+        );
+        assert_eq!(
+            Some(Joltage::from(11)),
+            bank.joltage(2, &SelectionRule::minimize())
+        );
+        assert_eq!(
+            Some(Joltage::from(111911112111)),
+            bank.joltage(12, &SelectionRule::minimize())
+        );
+        assert_eq!(
+            Some(Joltage::from(91)),
+            bank.joltage(2, &SelectionRule::maximize().with_max_gap(0))
+        );
+        assert_eq!(
+            Some(Joltage::from(11)),
+            bank.joltage(2, &SelectionRule::minimize().with_max_gap(usize::MAX))
+        );
+        assert_eq!(
+            Some(Joltage::from(88)),
+            bank.joltage(2, &SelectionRule::maximize().with_min_digit_count(8, 2))
+        );
+        assert_eq!(
+            Some(Joltage::from(19)),
+            bank.joltage(2, &SelectionRule::minimize().with_min_digit_count(9, 1))
+        );
+        assert_eq!(
+            None,
+            bank.joltage(2, &SelectionRule::maximize().with_min_digit_count(7, 1))
+        );
+        assert_eq!(
+            None,
+            bank.joltage(2, &SelectionRule::maximize().with_min_digit_count(9, 3))
+        );
+        assert_eq!(
+            None,
+            bank.joltage(
+                2,
+                &SelectionRule::maximize().with_min_digit_count(9, usize::MAX)
+            )
+        );
+        assert_eq!(None, bank.joltage(16, &SelectionRule::maximize()));
+    }
+
+    #[test]
+    fn test_joltage_dynamic_programming() {
+        for bank in random_banks() {
+            for active_batteries in 0..=bank.batteries.len() {
+                // Skip the greedy fast path to make sure the general solution agrees with it
+                assert_eq!(
+                    Some(bank.max_joltage(active_batteries).unwrap()),
+                    bank.select(active_batteries, &SelectionRule::maximize())
+                        .map(|indices| bank.joltage_from(&indices))
+                );
+
+                assert_eq!(
+                    brute_force_joltage(&bank.batteries, active_batteries, |_| true, true),
+                    bank.joltage(active_batteries, &SelectionRule::minimize())
+                );
+
+                for max_gap in 0..3 {
+                    assert_eq!(
+                        brute_force_joltage(
+                            &bank.batteries,
+                            active_batteries,
+                            |indices| indices.windows(2).all(|w| w[1] - w[0] <= max_gap + 1),
+                            false
+                        ),
+                        bank.joltage(
+                            active_batteries,
+                            &SelectionRule::maximize().with_max_gap(max_gap)
+                        )
+                    );
+                }
+
+                for count in 0..3 {
+                    assert_eq!(
+                        brute_force_joltage(
+                            &bank.batteries,
+                            active_batteries,
+                            |indices| indices.iter().filter(|&&i| bank.batteries[i] == 1).count()
+                                >= count,
+                            false
+                        ),
+                        bank.joltage(
+                            active_batteries,
+                            &SelectionRule::maximize().with_min_digit_count(1, count)
+                        )
+                    );
+                }
+            }
+        }
     }
 }
@@ -1,51 +1,248 @@
+use advent_of_code_2025::grid::{Grid, MOORE_OFFSETS, Position, VON_NEUMANN_OFFSETS};
+use std::collections::HashMap;
 use std::env;
 use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 use std::fs::File;
-use std::io::Read;
-use std::ops::Index;
+use std::io::{self, BufWriter, Read, Write};
+use std::ops::{Index, Range};
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::thread;
+
+const USAGE: &str = "Usage: day04 [--rounds] [--max-rounds ROUNDS] [--show] [--images DIRECTORY] [--image-format ppm|pgm] [--threshold THRESHOLD] [--neighborhood von-neumann|moore|radius:R|DX,DY[/DX,DY...]] [--edges empty|walls|wrap] [--backend tiles|bits] [--automaton removal|B.../S...] [--async] [--generations GENERATIONS] [--tiles TILE=CHARS[/TILE=CHARS...]] [--threads THREADS (initial scan with the tiles backend only)] INPUT_FILE_PATH";
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
+    let mut args = env::args().skip(1);
+
+    let mut path = None;
+    let mut show_rounds = false;
+    let mut max_rounds = None;
+    let mut show = false;
+    let mut image_directory = None;
+    let mut image_format = ImageFormat::Ppm;
+    let mut rule = MobilityRule::default();
+    let mut backend = Backend::Tiles;
+    let mut automaton = None;
+    let mut update = Update::Synchronous;
+    let mut max_generations = 10_000;
+    let mut tile_symbols = TileSymbols::default();
+    let mut threads = 1;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rounds" => show_rounds = true,
+            "--max-rounds" => max_rounds = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--show" => show = true,
+            "--images" => image_directory = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--image-format" => image_format = args.next().ok_or(USAGE)?.parse()?,
+            "--threshold" => rule = rule.with_threshold(args.next().ok_or(USAGE)?.parse()?),
+            "--neighborhood" => rule = rule.with_neighborhood(&args.next().ok_or(USAGE)?.parse()?),
+            "--edges" => rule = rule.with_edges(args.next().ok_or(USAGE)?.parse()?),
+            "--backend" => backend = args.next().ok_or(USAGE)?.parse()?,
+            "--automaton" => automaton = Some(args.next().ok_or(USAGE)?),
+            "--async" => update = Update::Asynchronous,
+            "--generations" => max_generations = args.next().ok_or(USAGE)?.parse()?,
+            "--tiles" => tile_symbols = args.next().ok_or(USAGE)?.parse()?,
+            "--threads" => threads = args.next().ok_or(USAGE)?.parse()?,
+            _ => path = Some(arg),
+        }
+    }
+
+    let show_removal = show_rounds || max_rounds.is_some() || show || image_directory.is_some();
+
+    if backend == Backend::Bits && show_removal {
+        return Err(
+            "The bits backend only counts rolls, so it can't be combined with --rounds, \
+            --max-rounds, --show, or --images"
+                .into(),
+        );
+    }
+
+    if backend == Backend::Bits && threads > 1 {
+        return Err("--threads only applies to the tiles backend".into());
+    }
 
-    if let Some(path) = args.get(1) {
+    if let Some(path) = path {
         let warehouse = {
             let mut warehouse_map = String::new();
             File::open(path)?.read_to_string(&mut warehouse_map)?;
 
-            Warehouse::from_str(warehouse_map.as_str())?
+            Warehouse::parse(warehouse_map.as_str(), &tile_symbols)?
+        };
+
+        if let Some(automaton) = automaton {
+            let automaton = match automaton.as_str() {
+                "removal" => Automaton::roll_removal(&rule),
+                life_rule => Automaton::new(life_rule.parse()?)
+                    .with_offsets(rule.offsets.clone())
+                    .with_edges(rule.edges),
+            }
+            .with_update(update);
+
+            let (warehouse, outcome) = automaton.run(&warehouse, max_generations);
+
+            match outcome {
+                Outcome::FixedPoint(generations) => {
+                    println!("Reached a fixed point after {} generations", generations)
+                }
+                Outcome::Cycle { start, period } => println!(
+                    "Entered a cycle of period {} at generation {}",
+                    period, start
+                ),
+                Outcome::Unsettled => {
+                    println!("Still changing after {} generations", max_generations)
+                }
+            }
+
+            if show {
+                println!("{}\n", warehouse);
+            }
+
+            println!("Rolls remaining: {}", warehouse.rolls());
+
+            return Ok(());
+        }
+
+        let bit_warehouse = match backend {
+            Backend::Tiles => None,
+            Backend::Bits => Some(BitWarehouse::from(&warehouse)),
+        };
+
+        let initial_movable_rolls = match &bit_warehouse {
+            Some(bit_warehouse) => bit_warehouse.movable_rolls(&rule),
+            None if threads > 1 => warehouse.movable_rolls_parallel(&rule, threads),
+            None => warehouse.movable_rolls(&rule),
         };
 
-        println!("Initial movable rolls: {}", warehouse.movable_rolls().len());
-        println!("Total movable rolls: {}", warehouse.minimize());
+        println!("Initial movable rolls: {}", initial_movable_rolls.len());
+
+        if show_removal {
+            let write_image = |round: usize, warehouse: &Warehouse| -> Result<(), Box<dyn Error>> {
+                if let Some(image_directory) = &image_directory {
+                    let path = image_directory.join(format!(
+                        "round-{:04}.{}",
+                        round,
+                        image_format.extension()
+                    ));
+
+                    warehouse.write_image(BufWriter::new(File::create(path)?), image_format)?;
+                }
+
+                Ok(())
+            };
+
+            let mut state = warehouse.clone();
+            let mut removed_rolls = 0;
+
+            if show {
+                println!("{}\n", state);
+            }
+
+            write_image(0, &state)?;
+
+            for (index, round) in warehouse
+                .removal_rounds(&rule)
+                .take(max_rounds.unwrap_or(usize::MAX))
+                .enumerate()
+            {
+                removed_rolls += round.removed.len();
+                state.remove_rolls(&round.removed);
+
+                if show_rounds {
+                    println!(
+                        "Round {}: removed {} rolls, {} remaining",
+                        index + 1,
+                        round.removed.len(),
+                        round.remaining_rolls
+                    );
+                }
+
+                if show {
+                    println!("{}\n", state);
+                }
+
+                write_image(index + 1, &state)?;
+            }
+
+            println!("Total movable rolls: {}", removed_rolls);
+        } else {
+            let total_movable_rolls = match &bit_warehouse {
+                Some(bit_warehouse) => bit_warehouse.minimize(&rule),
+                None => warehouse.minimize(&rule),
+            };
+
+            println!("Total movable rolls: {}", total_movable_rolls);
+        }
 
         Ok(())
     } else {
-        Err("Usage: day04 INPUT_FILE_PATH".into())
+        Err(USAGE.into())
     }
 }
 
+#[derive(Clone)]
 struct Warehouse {
-    width: usize,
-    tiles: Vec<Tile>,
+    tiles: Grid<Tile>,
 }
 
 impl Warehouse {
-    pub fn movable_rolls(&self) -> Vec<(usize, usize)> {
+    pub fn parse(s: &str, symbols: &TileSymbols) -> Result<Self, Box<dyn Error>> {
+        let mut width = None;
+        let mut cells = Vec::new();
+
+        // `lines` also strips the carriage return from CRLF line endings; blank lines at the very
+        // end are just trailing newlines, not a row with no tiles
+        for (index, line) in s.trim_end_matches(['\r', '\n']).lines().enumerate() {
+            let row = line
+                .chars()
+                .enumerate()
+                .map(|(column, c)| {
+                    symbols.tile(c).ok_or_else(|| {
+                        format!(
+                            "Unexpected character {:?} at line {}, column {}",
+                            c,
+                            index + 1,
+                            column + 1
+                        )
+                        .into()
+                    })
+                })
+                .collect::<Result<Vec<Tile>, Box<dyn Error>>>()?;
+
+            match width {
+                None => width = Some(row.len()),
+                Some(width) if width != row.len() => {
+                    return Err(format!(
+                        "Warehouse must be rectangular, but line {} has {} tiles instead of {}",
+                        index + 1,
+                        row.len(),
+                        width
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+
+            cells.extend(row);
+        }
+
+        Ok(Warehouse {
+            tiles: Grid::from_cells(width.unwrap_or(0), cells)?,
+        })
+    }
+
+    pub fn movable_rolls(&self, rule: &MobilityRule) -> Vec<Position> {
         let mut movable_rolls = Vec::new();
 
-        for x in 0..self.width {
-            for y in 0..self.height() {
-                if matches!(self[(x, y)], Tile::PaperRoll) {
-                    let adjacent_rolls = self
-                        .neighbors(x, y)
-                        .iter()
-                        .filter(|tile| matches!(tile, Tile::PaperRoll))
-                        .count();
-
-                    if adjacent_rolls < 4 {
-                        movable_rolls.push((x, y));
-                    }
+        for x in 0..self.tiles.width() {
+            for y in 0..self.tiles.height() {
+                let position = Position::new(x, y);
+
+                if matches!(self[position], Tile::PaperRoll)
+                    && self.adjacent_rolls(position, &rule.offsets, rule.edges) < rule.threshold
+                {
+                    movable_rolls.push(position);
                 }
             }
         }
@@ -53,138 +250,1657 @@ impl Warehouse {
         movable_rolls
     }
 
-    pub fn minimize(mut self) -> usize {
-        let initial_rolls = self
-            .tiles
-            .iter()
-            .filter(|tile| matches!(tile, Tile::PaperRoll))
-            .count();
+    // Scans bands of rows on separate threads, producing the same results in the same order as
+    // `movable_rolls`
+    pub fn movable_rolls_parallel(&self, rule: &MobilityRule, threads: usize) -> Vec<Position> {
+        let height = self.tiles.height();
+        let band_height = height.div_ceil(threads.max(1));
+
+        let mut movable_rolls: Vec<Position> = thread::scope(|scope| {
+            let bands: Vec<_> = (0..height)
+                .step_by(band_height)
+                .map(|start| {
+                    let rows = start..(start + band_height).min(height);
+                    scope.spawn(move || self.movable_rolls_in_rows(rule, rows))
+                })
+                .collect();
+
+            bands
+                .into_iter()
+                .flat_map(|band| band.join().expect("Scan thread panicked"))
+                .collect()
+        });
+
+        // Bands are scanned row by row, so restore the column-by-column order of the serial scan
+        movable_rolls.sort();
+        movable_rolls
+    }
 
-        let height = self.height();
+    fn movable_rolls_in_rows(&self, rule: &MobilityRule, rows: Range<usize>) -> Vec<Position> {
+        let mut movable_rolls = Vec::new();
+
+        for y in rows {
+            for x in 0..self.tiles.width() {
+                let position = Position::new(x, y);
+
+                if matches!(self[position], Tile::PaperRoll)
+                    && self.adjacent_rolls(position, &rule.offsets, rule.edges) < rule.threshold
+                {
+                    movable_rolls.push(position);
+                }
+            }
+        }
+
+        movable_rolls
+    }
+
+    pub fn minimize(&self, rule: &MobilityRule) -> usize {
+        self.removal_rounds(rule)
+            .map(|round| round.removed.len())
+            .sum()
+    }
+
+    pub fn removal_rounds(&self, rule: &MobilityRule) -> RemovalRounds {
+        RemovalRounds::new(self.clone(), rule.clone())
+    }
+
+    pub fn remove_rolls(&mut self, positions: &[Position]) {
+        for &position in positions {
+            self.tiles[position] = Tile::RemovedRoll;
+        }
+    }
+
+    pub fn write_image(&self, mut writer: impl Write, format: ImageFormat) -> io::Result<()> {
+        let magic_number = match format {
+            ImageFormat::Pgm => "P5",
+            ImageFormat::Ppm => "P6",
+        };
+
+        write!(
+            writer,
+            "{}\n{} {}\n255\n",
+            magic_number,
+            self.tiles.width(),
+            self.tiles.height()
+        )?;
+
+        for tile in self.tiles.cells() {
+            match format {
+                ImageFormat::Pgm => writer.write_all(&[tile.gray()])?,
+                ImageFormat::Ppm => writer.write_all(&tile.rgb())?,
+            }
+        }
+
+        writer.flush()
+    }
+
+    #[cfg(test)]
+    fn minimize_by_rescanning(mut self, rule: &MobilityRule) -> usize {
+        let initial_rolls = self.rolls();
 
         loop {
-            let movable_rolls = self.movable_rolls();
+            let movable_rolls = self.movable_rolls(rule);
 
             if movable_rolls.is_empty() {
                 break;
             }
 
-            for (x, y) in movable_rolls {
-                self.tiles[x + (y * height)] = Tile::Empty;
-            }
+            self.remove_rolls(&movable_rolls);
         }
 
-        let remaining_rolls = self
-            .tiles
+        initial_rolls - self.rolls()
+    }
+
+    fn adjacent_rolls(
+        &self,
+        position: Position,
+        offsets: &[(isize, isize)],
+        edges: EdgePolicy,
+    ) -> usize {
+        offsets
             .iter()
-            .filter(|tile| matches!(tile, Tile::PaperRoll))
-            .count();
+            .filter(|&&offset| match self.neighbor(position, offset, edges) {
+                Some(neighbor) => matches!(self[neighbor], Tile::PaperRoll),
+                None => edges == EdgePolicy::Walls,
+            })
+            .count()
+    }
+
+    // Returns the position at the given offset from `position`, or `None` if the offset leads off
+    // the edge of the warehouse
+    fn neighbor(
+        &self,
+        position: Position,
+        (dx, dy): (isize, isize),
+        edges: EdgePolicy,
+    ) -> Option<Position> {
+        match edges {
+            EdgePolicy::Wrap => Some(self.tiles.wrapping_offset(position, dx, dy)),
+            EdgePolicy::Empty | EdgePolicy::Walls => position
+                .offset(dx, dy)
+                .filter(|neighbor| self.tiles.contains(*neighbor)),
+        }
+    }
 
-        initial_rolls - remaining_rolls
+    fn rolls(&self) -> usize {
+        self.tiles
+            .cells()
+            .filter(|tile| matches!(tile, Tile::PaperRoll))
+            .count()
     }
+}
+
+struct RemovalRound {
+    removed: Vec<Position>,
+    remaining_rolls: usize,
+}
+
+struct RemovalRounds {
+    warehouse: Warehouse,
+    rule: MobilityRule,
+    adjacent_rolls: Grid<usize>,
+    movable_rolls: Vec<Position>,
+    remaining_rolls: usize,
+}
 
-    fn height(&self) -> usize {
-        self.tiles.len() / self.width
+impl RemovalRounds {
+    fn new(warehouse: Warehouse, rule: MobilityRule) -> Self {
+        // Rather than rescanning the whole warehouse after each round, keep track of how many
+        // adjacent rolls each roll has and only revisit the neighbors of rolls we've just removed
+        let mut adjacent_rolls = warehouse.tiles.map(|_| 0);
+        let mut movable_rolls = Vec::new();
+        let mut remaining_rolls = 0;
+
+        for position in warehouse.tiles.positions() {
+            if matches!(warehouse[position], Tile::PaperRoll) {
+                adjacent_rolls[position] =
+                    warehouse.adjacent_rolls(position, &rule.offsets, rule.edges);
+                remaining_rolls += 1;
+
+                if adjacent_rolls[position] < rule.threshold {
+                    movable_rolls.push(position);
+                }
+            }
+        }
+
+        RemovalRounds {
+            warehouse,
+            rule,
+            adjacent_rolls,
+            movable_rolls,
+            remaining_rolls,
+        }
     }
+}
+
+impl Iterator for RemovalRounds {
+    type Item = RemovalRound;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.movable_rolls.is_empty() {
+            return None;
+        }
 
-    fn neighbors(&self, x: usize, y: usize) -> Vec<&Tile> {
-        let mut neighbors = Vec::new();
+        let mut removed = std::mem::take(&mut self.movable_rolls);
+        removed.sort();
 
-        let min_x = if x == 0 { 0 } else { x - 1 };
-        let max_x = (x + 1).min(self.width - 1);
-        let min_y = if y == 0 { 0 } else { y - 1 };
-        let max_y = (y + 1).min(self.height() - 1);
+        // Rolls in a round are all removed at once, so clear them all before updating neighbors
+        self.warehouse.remove_rolls(&removed);
 
-        for a in min_x..=max_x {
-            for b in min_y..=max_y {
-                if (a, b) != (x, y) {
-                    neighbors.push(&self[(a, b)]);
+        for &position in &removed {
+            // Neighborhoods aren't necessarily symmetric, so look for the rolls that have this
+            // position as a neighbor rather than this position's own neighbors
+            for &(dx, dy) in &self.rule.offsets {
+                if let Some(neighbor) =
+                    self.warehouse
+                        .neighbor(position, (-dx, -dy), self.rule.edges)
+                    && matches!(self.warehouse[neighbor], Tile::PaperRoll)
+                {
+                    self.adjacent_rolls[neighbor] -= 1;
+
+                    // Every roll below the threshold was removed in this round, so anything that
+                    // just dropped below the threshold is newly movable
+                    if self.adjacent_rolls[neighbor] + 1 == self.rule.threshold {
+                        self.movable_rolls.push(neighbor);
+                    }
                 }
             }
         }
 
-        neighbors
+        self.remaining_rolls -= removed.len();
+
+        Some(RemovalRound {
+            removed,
+            remaining_rolls: self.remaining_rolls,
+        })
     }
 }
 
-impl Index<(usize, usize)> for Warehouse {
-    type Output = Tile;
+#[derive(Clone, Debug)]
+struct MobilityRule {
+    // Rolls are movable if fewer than this many of their neighbors are rolls
+    threshold: usize,
+    offsets: Vec<(isize, isize)>,
+    edges: EdgePolicy,
+}
 
-    fn index(&self, index: (usize, usize)) -> &Self::Output {
-        let (x, y) = index;
+impl MobilityRule {
+    pub fn with_threshold(self, threshold: usize) -> Self {
+        MobilityRule { threshold, ..self }
+    }
 
-        if x >= self.width || y >= self.height() {
-            panic!("Index out of bounds");
+    pub fn with_neighborhood(self, neighborhood: &Neighborhood) -> Self {
+        MobilityRule {
+            offsets: neighborhood.offsets(),
+            ..self
         }
+    }
 
-        &self.tiles[x + (y * self.height())]
+    pub fn with_edges(self, edges: EdgePolicy) -> Self {
+        MobilityRule { edges, ..self }
     }
 }
 
-impl FromStr for Warehouse {
+impl Default for MobilityRule {
+    fn default() -> Self {
+        MobilityRule {
+            threshold: 4,
+            offsets: MOORE_OFFSETS.to_vec(),
+            edges: EdgePolicy::Empty,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Neighborhood {
+    VonNeumann,
+    Moore,
+    Radius(usize),
+    Custom(Vec<(isize, isize)>),
+}
+
+impl Neighborhood {
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        match self {
+            Neighborhood::VonNeumann => VON_NEUMANN_OFFSETS.to_vec(),
+            Neighborhood::Moore => MOORE_OFFSETS.to_vec(),
+            Neighborhood::Radius(radius) => {
+                let radius = *radius as isize;
+
+                (-radius..=radius)
+                    .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+                    .filter(|&offset| offset != (0, 0))
+                    .collect()
+            }
+            Neighborhood::Custom(offsets) => offsets.clone(),
+        }
+    }
+}
+
+impl FromStr for Neighborhood {
     type Err = Box<dyn Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let width = s
-            .lines()
-            .next()
-            .ok_or("Warehouse string must have at least one line")?
-            .len();
-
-        let tiles: Vec<Tile> = s
-            .chars()
-            .filter_map(|c| match c {
-                '.' => Some(Tile::Empty),
-                '@' => Some(Tile::PaperRoll),
-                _ => None,
-            })
-            .collect();
+        match s {
+            "von-neumann" => Ok(Neighborhood::VonNeumann),
+            "moore" => Ok(Neighborhood::Moore),
+            _ => {
+                if let Some(radius) = s.strip_prefix("radius:") {
+                    return Ok(Neighborhood::Radius(radius.parse()?));
+                }
 
-        if !tiles.len().is_multiple_of(width) {
-            return Err("Warehouse must be rectangular".into());
-        }
+                let offsets = s
+                    .split('/')
+                    .map(|offset| {
+                        let (dx, dy) = offset
+                            .split_once(',')
+                            .ok_or("Neighborhood offsets must be of the form DX,DY")?;
+
+                        match (dx.trim().parse()?, dy.trim().parse()?) {
+                            (0, 0) => Err("Neighborhood offsets must not include (0, 0)".into()),
+                            offset => Ok(offset),
+                        }
+                    })
+                    .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
 
-        Ok(Warehouse { width, tiles })
+                Ok(Neighborhood::Custom(offsets))
+            }
+        }
     }
 }
 
-enum Tile {
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum EdgePolicy {
+    // Tiles beyond the edge of the warehouse are empty
+    #[default]
     Empty,
-    PaperRoll,
+    // Tiles beyond the edge of the warehouse count as rolls
+    Walls,
+    // The warehouse wraps around at its edges
+    Wrap,
 }
 
-#[cfg(test)]
-mod test {
-    use crate::Warehouse;
-    use indoc::indoc;
-    use std::str::FromStr;
+impl FromStr for EdgePolicy {
+    type Err = Box<dyn Error>;
 
-    const TEST_WAREHOUSE: &str = indoc! {"
-        ..@@.@@@@.
-        @@@.@.@.@@
-        @@@@@.@.@@
-        @.@@@@..@.
-        @@.@@@@.@@
-        .@@@@@@@.@
-        .@.@.@.@@@
-        @.@@@.@@@@
-        .@@@@@@@@.
-        @.@.@@@.@.
-    "};
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "empty" => Ok(EdgePolicy::Empty),
+            "walls" => Ok(EdgePolicy::Walls),
+            "wrap" => Ok(EdgePolicy::Wrap),
+            _ => Err("Edge policy must be one of \"empty\", \"walls\", or \"wrap\"".into()),
+        }
+    }
+}
 
-    #[test]
-    fn test_movable_rolls() {
-        assert_eq!(
-            13,
-            Warehouse::from_str(TEST_WAREHOUSE)
-                .unwrap()
-                .movable_rolls()
-                .len()
-        );
+// A bit-packed copy of a warehouse's rolls that can count the neighbors of 64 tiles at a time
+#[derive(Clone)]
+struct BitWarehouse {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    // One bit per tile, set if the tile holds a roll; each row is padded out to a whole number of
+    // words, and padding bits are always clear
+    rows: Vec<u64>,
+    // A row with a bit set for every tile in the warehouse
+    full_row: Vec<u64>,
+    empty_row: Vec<u64>,
+}
+
+impl BitWarehouse {
+    pub fn movable_rolls(&self, rule: &MobilityRule) -> Vec<Position> {
+        let mut counters = vec![0; Self::counter_bits(rule)];
+        let mut movable_rolls = Vec::new();
+
+        for y in 0..self.height {
+            for w in 0..self.words_per_row {
+                let mut movable = self.movable_word(y, w, rule, &mut counters);
+
+                while movable != 0 {
+                    movable_rolls.push(Position::new(
+                        (w * 64) + movable.trailing_zeros() as usize,
+                        y,
+                    ));
+
+                    movable &= movable - 1;
+                }
+            }
+        }
+
+        // Match the column-by-column order of the tile-based scan
+        movable_rolls.sort();
+        movable_rolls
     }
 
-    #[test]
-    fn test_minimize() {
-        assert_eq!(43, Warehouse::from_str(TEST_WAREHOUSE).unwrap().minimize());
+    pub fn minimize(&self, rule: &MobilityRule) -> usize {
+        let mut warehouse = self.clone();
+        let mut counters = vec![0; Self::counter_bits(rule)];
+        let mut removed_rolls = 0;
+
+        let mut row_dys: Vec<isize> = rule.offsets.iter().map(|&(_, dy)| dy).collect();
+        row_dys.sort();
+        row_dys.dedup();
+
+        // Only rows with a removed roll in their neighborhood can have new movable rolls
+        let mut dirty_rows = vec![true; self.height];
+        let mut movable_words = Vec::new();
+
+        loop {
+            for y in (0..self.height).filter(|&y| dirty_rows[y]) {
+                for w in 0..self.words_per_row {
+                    let movable = warehouse.movable_word(y, w, rule, &mut counters);
+
+                    if movable != 0 {
+                        movable_words.push((y, w, movable));
+                    }
+                }
+            }
+
+            if movable_words.is_empty() {
+                break;
+            }
+
+            dirty_rows.fill(false);
+
+            // All of the movable rolls in a round are removed at once
+            for (y, w, movable) in movable_words.drain(..) {
+                warehouse.rows[(y * self.words_per_row) + w] &= !movable;
+                removed_rolls += movable.count_ones() as usize;
+
+                for dy in &row_dys {
+                    if let Some(row) = warehouse.row_index(y, -dy, rule.edges) {
+                        dirty_rows[row] = true;
+                    }
+                }
+            }
+        }
+
+        removed_rolls
+    }
+
+    // Returns a word with a bit set for each roll in the given word of the given row that has
+    // fewer than the threshold number of neighboring rolls
+    fn movable_word(&self, y: usize, w: usize, rule: &MobilityRule, counters: &mut [u64]) -> u64 {
+        let rolls = self.rows[(y * self.words_per_row) + w];
+
+        if rolls == 0 {
+            return 0;
+        }
+
+        // Keep a separate counter for each of the 64 tiles in this word as a "bit-sliced" binary
+        // number; `counters[i]` holds bit `i` of every tile's count
+        counters.fill(0);
+
+        for &offset in &rule.offsets {
+            let mut carry = self.neighbor_word(y, w, offset, rule.edges);
+
+            for counter in counters.iter_mut() {
+                (*counter, carry) = (*counter ^ carry, *counter & carry);
+            }
+        }
+
+        rolls & Self::less_than(counters, rule.threshold)
+    }
+
+    // Returns a word with a bit set for each tile in the given word of the given row whose
+    // neighbor at the given offset is a roll
+    fn neighbor_word(
+        &self,
+        y: usize,
+        w: usize,
+        (dx, dy): (isize, isize),
+        edges: EdgePolicy,
+    ) -> u64 {
+        let row = match self.row_index(y, dy, edges) {
+            Some(row) => &self.rows[row * self.words_per_row..(row + 1) * self.words_per_row],
+            None if edges == EdgePolicy::Walls => &self.full_row,
+            None => &self.empty_row,
+        };
+
+        match edges {
+            EdgePolicy::Empty => Self::shifted_word(row, w, dx),
+            EdgePolicy::Walls => {
+                // Tiles whose neighbor would be beyond the left or right edge see a wall instead
+                Self::shifted_word(row, w, dx)
+                    | (self.full_row[w] & !Self::shifted_word(&self.full_row, w, dx))
+            }
+            EdgePolicy::Wrap => {
+                let dx = dx.rem_euclid(self.width as isize);
+
+                Self::shifted_word(row, w, dx)
+                    | Self::shifted_word(row, w, dx - self.width as isize)
+            }
+        }
+    }
+
+    // Returns the index of the row at the given vertical offset from `y`, or `None` if the offset
+    // leads off the edge of the warehouse
+    fn row_index(&self, y: usize, dy: isize, edges: EdgePolicy) -> Option<usize> {
+        match edges {
+            EdgePolicy::Wrap => Some((y as isize + dy).rem_euclid(self.height as isize) as usize),
+            EdgePolicy::Empty | EdgePolicy::Walls => {
+                y.checked_add_signed(dy).filter(|&row| row < self.height)
+            }
+        }
+    }
+
+    // Returns the given word of `row` shifted such that bit `b` of the result is bit `b + dx` of
+    // the word, pulling in bits from neighboring words as needed and treating bits outside the row
+    // as clear
+    fn shifted_word(row: &[u64], w: usize, dx: isize) -> u64 {
+        let bit = ((w * 64) as isize) + dx;
+        let (word, shift) = (bit.div_euclid(64), bit.rem_euclid(64) as u32);
+
+        let get = |i: isize| {
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| row.get(i))
+                .copied()
+                .unwrap_or(0)
+        };
+
+        if shift == 0 {
+            get(word)
+        } else {
+            (get(word) >> shift) | (get(word + 1) << (64 - shift))
+        }
+    }
+
+    // Returns a word with a bit set for each tile whose bit-sliced count is less than `threshold`
+    fn less_than(counters: &[u64], threshold: usize) -> u64 {
+        if threshold >> counters.len() != 0 {
+            // The threshold is larger than any count we could have
+            return !0;
+        }
+
+        let mut less = 0;
+        let mut equal = !0;
+
+        for (i, counter) in counters.iter().enumerate().rev() {
+            if threshold & (1 << i) != 0 {
+                less |= equal & !counter;
+                equal &= counter;
+            } else {
+                equal &= !counter;
+            }
+        }
+
+        less
+    }
+
+    // Returns the number of bits needed to count every neighbor in the rule's neighborhood
+    fn counter_bits(rule: &MobilityRule) -> usize {
+        (usize::BITS - rule.offsets.len().leading_zeros()) as usize
+    }
+}
+
+impl From<&Warehouse> for BitWarehouse {
+    fn from(warehouse: &Warehouse) -> Self {
+        let width = warehouse.tiles.width();
+        let words_per_row = width.div_ceil(64);
+
+        let mut rows = vec![0; words_per_row * warehouse.tiles.height()];
+        let mut full_row = vec![0; words_per_row];
+
+        for x in 0..width {
+            full_row[x / 64] |= 1 << (x % 64);
+        }
+
+        for (position, tile) in warehouse.tiles.iter() {
+            if matches!(tile, Tile::PaperRoll) {
+                rows[(position.y * words_per_row) + (position.x / 64)] |= 1 << (position.x % 64);
+            }
+        }
+
+        BitWarehouse {
+            width,
+            height: warehouse.tiles.height(),
+            words_per_row,
+            rows,
+            full_row,
+            empty_row: vec![0; words_per_row],
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Backend {
+    Tiles,
+    Bits,
+}
+
+impl FromStr for Backend {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tiles" => Ok(Backend::Tiles),
+            "bits" => Ok(Backend::Bits),
+            _ => Err("Backend must be either \"tiles\" or \"bits\"".into()),
+        }
+    }
+}
+
+// A Life-like rule: dead tiles become rolls if their number of neighboring rolls is in `birth`,
+// and rolls survive if their number of neighboring rolls is in `survival`
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct LifeRule {
+    birth: Vec<usize>,
+    survival: Vec<usize>,
+}
+
+impl FromStr for LifeRule {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut birth = None;
+        let mut survival = None;
+
+        let parse_counts = |counts: &str| -> Result<Vec<usize>, Box<dyn Error>> {
+            counts
+                .chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .map(|count| count as usize)
+                        .ok_or_else(|| format!("Bad neighbor count in rule: {}", c).into())
+                })
+                .collect()
+        };
+
+        for part in s.split('/') {
+            if let Some(counts) = part.strip_prefix(['B', 'b']) {
+                birth = Some(parse_counts(counts)?);
+            } else if let Some(counts) = part.strip_prefix(['S', 's']) {
+                survival = Some(parse_counts(counts)?);
+            } else {
+                return Err(format!("Rule must be of the form B.../S..., but got {}", s).into());
+            }
+        }
+
+        Ok(LifeRule {
+            birth: birth.ok_or("Rule must have a birth component")?,
+            survival: survival.ok_or("Rule must have a survival component")?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum Update {
+    // Every tile's next state is computed from the previous generation
+    #[default]
+    Synchronous,
+    // Tiles are updated in place one at a time in row-major order, and later tiles see the new
+    // states of earlier tiles
+    Asynchronous,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Outcome {
+    // The warehouse stopped changing after the given number of generations
+    FixedPoint(usize),
+    // The warehouse at generation `start + period` is the same as at generation `start`
+    Cycle { start: usize, period: usize },
+    // The warehouse was still changing when we ran out of generations
+    Unsettled,
+}
+
+#[derive(Clone, Debug)]
+struct Automaton {
+    rule: LifeRule,
+    offsets: Vec<(isize, isize)>,
+    edges: EdgePolicy,
+    update: Update,
+}
+
+impl Automaton {
+    pub fn new(rule: LifeRule) -> Self {
+        Automaton {
+            rule,
+            offsets: MOORE_OFFSETS.to_vec(),
+            edges: EdgePolicy::Empty,
+            update: Update::Synchronous,
+        }
+    }
+
+    // Rolls never appear, and only survive if they have at least the threshold number of
+    // neighboring rolls
+    pub fn roll_removal(rule: &MobilityRule) -> Self {
+        Automaton {
+            rule: LifeRule {
+                birth: vec![],
+                survival: (rule.threshold..=rule.offsets.len()).collect(),
+            },
+            offsets: rule.offsets.clone(),
+            edges: rule.edges,
+            update: Update::Synchronous,
+        }
+    }
+
+    pub fn with_offsets(self, offsets: Vec<(isize, isize)>) -> Self {
+        Automaton { offsets, ..self }
+    }
+
+    pub fn with_edges(self, edges: EdgePolicy) -> Self {
+        Automaton { edges, ..self }
+    }
+
+    pub fn with_update(self, update: Update) -> Self {
+        Automaton { update, ..self }
+    }
+
+    // Advances the warehouse by one generation, returning the number of tiles that changed
+    pub fn step(&self, warehouse: &mut Warehouse) -> usize {
+        match self.update {
+            Update::Synchronous => {
+                let changes: Vec<(Position, Tile)> = warehouse
+                    .tiles
+                    .positions()
+                    .filter_map(|position| {
+                        self.next_tile(warehouse, position)
+                            .map(|tile| (position, tile))
+                    })
+                    .collect();
+
+                for &(position, tile) in &changes {
+                    warehouse.tiles[position] = tile;
+                }
+
+                changes.len()
+            }
+            Update::Asynchronous => {
+                let mut changes = 0;
+
+                for position in warehouse.tiles.positions() {
+                    if let Some(tile) = self.next_tile(warehouse, position) {
+                        warehouse.tiles[position] = tile;
+                        changes += 1;
+                    }
+                }
+
+                changes
+            }
+        }
+    }
+
+    // Runs the automaton until it reaches a fixed point or a cycle or until `max_generations`
+    // generations have passed, returning the final state of the warehouse and how the run ended
+    pub fn run(&self, warehouse: &Warehouse, max_generations: usize) -> (Warehouse, Outcome) {
+        let mut warehouse = warehouse.clone();
+
+        // Keeping every generation around as tiles could get expensive for large warehouses, so
+        // key each generation on its rolls packed one bit per tile instead; hashing alone could
+        // report a cycle on a collision between two different generations
+        let mut generations_by_rolls = HashMap::new();
+
+        for generation in 0..=max_generations {
+            let rolls = BitWarehouse::from(&warehouse).rows;
+
+            if let Some(&start) = generations_by_rolls.get(&rolls) {
+                return (
+                    warehouse,
+                    Outcome::Cycle {
+                        start,
+                        period: generation - start,
+                    },
+                );
+            }
+
+            generations_by_rolls.insert(rolls, generation);
+
+            if generation == max_generations {
+                break;
+            }
+
+            if self.step(&mut warehouse) == 0 {
+                return (warehouse, Outcome::FixedPoint(generation));
+            }
+        }
+
+        (warehouse, Outcome::Unsettled)
+    }
+
+    // Returns the next state of the tile at the given position, or `None` if it doesn't change
+    fn next_tile(&self, warehouse: &Warehouse, position: Position) -> Option<Tile> {
+        let adjacent_rolls = warehouse.adjacent_rolls(position, &self.offsets, self.edges);
+
+        match warehouse[position] {
+            Tile::PaperRoll if !self.rule.survival.contains(&adjacent_rolls) => {
+                Some(Tile::RemovedRoll)
+            }
+            Tile::Empty | Tile::RemovedRoll if self.rule.birth.contains(&adjacent_rolls) => {
+                Some(Tile::PaperRoll)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Index<Position> for Warehouse {
+    type Output = Tile;
+
+    fn index(&self, position: Position) -> &Self::Output {
+        &self.tiles[position]
+    }
+}
+
+impl FromStr for Warehouse {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Warehouse::parse(s, &TileSymbols::default())
+    }
+}
+
+impl Display for Warehouse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.tiles.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for tile in row {
+                write!(f, "{}", tile.symbol())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Tile {
+    Empty,
+    PaperRoll,
+    RemovedRoll,
+}
+
+impl Tile {
+    fn symbol(&self) -> char {
+        match self {
+            Tile::Empty => '.',
+            Tile::PaperRoll => '@',
+            Tile::RemovedRoll => 'x',
+        }
+    }
+
+    fn gray(&self) -> u8 {
+        match self {
+            Tile::Empty => 255,
+            Tile::PaperRoll => 0,
+            Tile::RemovedRoll => 160,
+        }
+    }
+
+    fn rgb(&self) -> [u8; 3] {
+        match self {
+            Tile::Empty => [255, 255, 255],
+            Tile::PaperRoll => [139, 94, 60],
+            Tile::RemovedRoll => [220, 40, 40],
+        }
+    }
+}
+
+// Maps characters in a warehouse map to the tiles they represent
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct TileSymbols {
+    tiles: HashMap<char, Tile>,
+}
+
+impl TileSymbols {
+    fn tile(&self, c: char) -> Option<Tile> {
+        self.tiles.get(&c).copied()
+    }
+}
+
+impl Default for TileSymbols {
+    fn default() -> Self {
+        TileSymbols {
+            tiles: [Tile::Empty, Tile::PaperRoll, Tile::RemovedRoll]
+                .into_iter()
+                .map(|tile| (tile.symbol(), tile))
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for TileSymbols {
+    type Err = Box<dyn Error>;
+
+    // Parses mappings like `empty=._/roll=@#`, which replace the default mapping entirely
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tiles = HashMap::new();
+
+        for mapping in s.split('/') {
+            let (tile, symbols) = mapping
+                .split_once('=')
+                .ok_or("Tile mappings must be of the form TILE=CHARS")?;
+
+            let tile = match tile {
+                "empty" => Tile::Empty,
+                "roll" => Tile::PaperRoll,
+                "removed" => Tile::RemovedRoll,
+                _ => return Err(format!("Unrecognized tile: {}", tile).into()),
+            };
+
+            for c in symbols.chars() {
+                if tiles
+                    .insert(c, tile)
+                    .is_some_and(|existing| existing != tile)
+                {
+                    return Err(format!("Character {:?} maps to more than one tile", c).into());
+                }
+            }
+        }
+
+        Ok(TileSymbols { tiles })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum ImageFormat {
+    Pgm,
+    Ppm,
+}
+
+impl ImageFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Pgm => "pgm",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pgm" => Ok(ImageFormat::Pgm),
+            "ppm" => Ok(ImageFormat::Ppm),
+            _ => Err("Image format must be either \"ppm\" or \"pgm\"".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        Automaton, BitWarehouse, EdgePolicy, ImageFormat, LifeRule, MobilityRule, Neighborhood,
+        Outcome, RemovalRound, Tile, TileSymbols, Update, Warehouse,
+    };
+    use advent_of_code_2025::grid::Position;
+    use advent_of_code_2025::random;
+    use indoc::indoc;
+    use std::str::FromStr;
+
+    const TEST_WAREHOUSE: &str = indoc! {"
+        ..@@.@@@@.
+        @@@.@.@.@@
+        @@@@@.@.@@
+        @.@@@@..@.
+        @@.@@@@.@@
+        .@@@@@@@.@
+        .@.@.@.@@@
+        @.@@@.@@@@
+        .@@@@@@@@.
+        @.@.@@@.@.
+    "};
+
+    #[test]
+    fn test_movable_rolls() {
+        assert_eq!(
+            13,
+            Warehouse::from_str(TEST_WAREHOUSE)
+                .unwrap()
+                .movable_rolls(&MobilityRule::default())
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_movable_rolls_parallel() {
+        for map in [
+            TEST_WAREHOUSE,
+            "@@@..\n...@.\n",
+            "@@@@@@@@\n@@@@@@@@\n@@@@@@@@\n",
+            "@@.\n@@@\n@@@\n@@@\n@@@\n.@@\n",
+        ] {
+            let warehouse = Warehouse::from_str(map).unwrap();
+            let movable_rolls = warehouse.movable_rolls(&MobilityRule::default());
+
+            // Include more threads than rows
+            for threads in [0, 1, 2, 3, 4, 7, 16] {
+                assert_eq!(
+                    movable_rolls,
+                    warehouse.movable_rolls_parallel(&MobilityRule::default(), threads),
+                    "{} threads\n{}",
+                    threads,
+                    map
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimize() {
+        assert_eq!(
+            43,
+            Warehouse::from_str(TEST_WAREHOUSE)
+                .unwrap()
+                .minimize(&MobilityRule::default())
+        );
+        assert_eq!(
+            43,
+            Warehouse::from_str(TEST_WAREHOUSE)
+                .unwrap()
+                .minimize_by_rescanning(&MobilityRule::default())
+        );
+    }
+
+    #[test]
+    fn test_removal_rounds() {
+        let warehouse = Warehouse::from_str(TEST_WAREHOUSE).unwrap();
+
+        let rounds: Vec<RemovalRound> =
+            warehouse.removal_rounds(&MobilityRule::default()).collect();
+
+        assert_eq!(
+            warehouse.movable_rolls(&MobilityRule::default()),
+            rounds[0].removed
+        );
+
+        assert_eq!(
+            vec![13, 12, 7, 5, 2, 1, 1, 1, 1],
+            rounds
+                .iter()
+                .map(|round| round.removed.len())
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            vec![58, 46, 39, 34, 32, 31, 30, 29, 28],
+            rounds
+                .iter()
+                .map(|round| round.remaining_rolls)
+                .collect::<Vec<_>>()
+        );
+
+        // Stopping early shouldn't affect the rounds we do see
+        assert_eq!(
+            25,
+            warehouse
+                .removal_rounds(&MobilityRule::default())
+                .take(2)
+                .map(|round| round.removed.len())
+                .sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let mut warehouse = Warehouse::from_str(TEST_WAREHOUSE).unwrap();
+
+        assert_eq!(TEST_WAREHOUSE.trim_end(), warehouse.to_string());
+
+        let round = warehouse
+            .removal_rounds(&MobilityRule::default())
+            .next()
+            .unwrap();
+        warehouse.remove_rolls(&round.removed);
+
+        // The first step from the puzzle's worked example
+        assert_eq!(
+            indoc! {"
+                ..xx.xx@x.
+                x@@.@.@.@@
+                @@@@@.x.@@
+                @.@@@@..@.
+                x@.@@@@.@x
+                .@@@@@@@.@
+                .@.@.@.@@@
+                x.@@@.@@@@
+                .@@@@@@@@.
+                x.x.@@@.x.
+            "}
+            .trim_end(),
+            warehouse.to_string()
+        );
+
+        // Removed rolls should survive a round trip through the parser
+        assert_eq!(
+            warehouse.to_string(),
+            Warehouse::from_str(&warehouse.to_string())
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_image() {
+        let mut warehouse = Warehouse::from_str("@@.\n..@\n").unwrap();
+        warehouse.remove_rolls(&[Position::new(1, 0)]);
+
+        let mut pgm = Vec::new();
+        warehouse.write_image(&mut pgm, ImageFormat::Pgm).unwrap();
+
+        assert_eq!(b"P5\n3 2\n255\n", &pgm[..11]);
+        assert_eq!(
+            vec![
+                Tile::PaperRoll.gray(),
+                Tile::RemovedRoll.gray(),
+                Tile::Empty.gray(),
+                Tile::Empty.gray(),
+                Tile::Empty.gray(),
+                Tile::PaperRoll.gray(),
+            ],
+            pgm[11..]
+        );
+
+        let mut ppm = Vec::new();
+        warehouse.write_image(&mut ppm, ImageFormat::Ppm).unwrap();
+
+        assert_eq!(b"P6\n3 2\n255\n", &ppm[..11]);
+        assert_eq!(11 + 3 * 6, ppm.len());
+        assert_eq!(Tile::RemovedRoll.rgb(), ppm[14..17]);
+    }
+
+    #[test]
+    fn test_minimize_differential() {
+        let mut next_random = random::generator::<u64>(0xd1b5_4a32_d192_ed03);
+
+        for _ in 0..500 {
+            let width = 1 + next_random(24) as usize;
+            let height = 1 + next_random(24) as usize;
+
+            let map: String = (0..height)
+                .map(|_| {
+                    (0..width)
+                        .map(|_| if next_random(4) == 0 { '.' } else { '@' })
+                        .chain(std::iter::once('\n'))
+                        .collect::<String>()
+                })
+                .collect();
+
+            let neighborhood = match next_random(4) {
+                0 => Neighborhood::VonNeumann,
+                1 => Neighborhood::Moore,
+                2 => Neighborhood::Radius(next_random(4) as usize),
+                _ => Neighborhood::Custom(
+                    (0..1 + next_random(6))
+                        .map(|_| (next_random(7) as isize - 3, next_random(7) as isize - 3))
+                        .filter(|&offset| offset != (0, 0))
+                        .collect(),
+                ),
+            };
+
+            let edges = match next_random(3) {
+                0 => EdgePolicy::Empty,
+                1 => EdgePolicy::Walls,
+                _ => EdgePolicy::Wrap,
+            };
+
+            let rule = MobilityRule::default()
+                .with_threshold(next_random(10) as usize)
+                .with_neighborhood(&neighborhood)
+                .with_edges(edges);
+
+            let warehouse = Warehouse::from_str(&map).unwrap();
+            let bit_warehouse = BitWarehouse::from(&warehouse);
+            let movable_rolls = warehouse.movable_rolls(&rule);
+
+            assert_eq!(
+                movable_rolls,
+                bit_warehouse.movable_rolls(&rule),
+                "{:?}\n{}",
+                rule,
+                map
+            );
+
+            assert_eq!(
+                movable_rolls,
+                warehouse.movable_rolls_parallel(&rule, 1 + next_random(8) as usize),
+                "{:?}\n{}",
+                rule,
+                map
+            );
+
+            let removed_rolls = warehouse.minimize(&rule);
+
+            assert_eq!(
+                removed_rolls,
+                bit_warehouse.minimize(&rule),
+                "{:?}\n{}",
+                rule,
+                map
+            );
+
+            assert_eq!(
+                removed_rolls,
+                warehouse.minimize_by_rescanning(&rule),
+                "{:?}\n{}",
+                rule,
+                map
+            );
+        }
+    }
+
+    #[test]
+    fn test_bit_warehouse() {
+        let warehouse = Warehouse::from_str(TEST_WAREHOUSE).unwrap();
+        let bit_warehouse = BitWarehouse::from(&warehouse);
+
+        assert_eq!(
+            warehouse.movable_rolls(&MobilityRule::default()),
+            bit_warehouse.movable_rolls(&MobilityRule::default())
+        );
+        assert_eq!(43, bit_warehouse.minimize(&MobilityRule::default()));
+
+        // Rows that span several words
+        let warehouse = Warehouse::from_str(&format!("{0}\n{0}\n{0}\n", "@".repeat(150))).unwrap();
+        let bit_warehouse = BitWarehouse::from(&warehouse);
+
+        for edges in [EdgePolicy::Empty, EdgePolicy::Walls, EdgePolicy::Wrap] {
+            let rule = MobilityRule::default().with_edges(edges);
+
+            assert_eq!(
+                warehouse.movable_rolls(&rule),
+                bit_warehouse.movable_rolls(&rule)
+            );
+            assert_eq!(warehouse.minimize(&rule), bit_warehouse.minimize(&rule));
+        }
+    }
+
+    // Run with `cargo test --release --bin day04 -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_bit_warehouse() {
+        use std::time::Instant;
+
+        const SIZE: usize = 10_000;
+
+        let mut next_random = random::generator::<u64>(0xaef1_7502_108e_f2d9);
+
+        let map: String = (0..SIZE)
+            .map(|_| {
+                (0..SIZE)
+                    .map(|_| if next_random(4) == 0 { '.' } else { '@' })
+                    .chain(std::iter::once('\n'))
+                    .collect::<String>()
+            })
+            .collect();
+
+        let warehouse = Warehouse::from_str(&map).unwrap();
+        drop(map);
+
+        let start = Instant::now();
+        let bit_warehouse = BitWarehouse::from(&warehouse);
+        let conversion_time = start.elapsed();
+
+        for (name, rule) in [
+            ("moore", MobilityRule::default()),
+            (
+                "von neumann",
+                MobilityRule::default()
+                    .with_neighborhood(&Neighborhood::VonNeumann)
+                    .with_threshold(3),
+            ),
+        ] {
+            let start = Instant::now();
+            let tiles = warehouse.minimize(&rule);
+            let tiles_time = start.elapsed();
+
+            let start = Instant::now();
+            let bits = bit_warehouse.minimize(&rule);
+            let bits_time = start.elapsed();
+
+            assert_eq!(tiles, bits);
+
+            println!(
+                "{:>12}: tiles {:>12?}, bits {:>12?} (+{:?} to convert)",
+                name, tiles_time, bits_time, conversion_time
+            );
+        }
+    }
+
+    #[test]
+    fn test_automaton_roll_removal() {
+        let warehouse = Warehouse::from_str(TEST_WAREHOUSE).unwrap();
+        let automaton = Automaton::roll_removal(&MobilityRule::default());
+
+        let (final_warehouse, outcome) = automaton.run(&warehouse, 100);
+
+        // One generation per removal round
+        assert_eq!(Outcome::FixedPoint(9), outcome);
+        assert_eq!(71 - 43, final_warehouse.rolls());
+
+        // Updating in place erodes the pile faster, but leaves the same rolls behind
+        let (async_warehouse, outcome) = automaton
+            .with_update(Update::Asynchronous)
+            .run(&warehouse, 100);
+
+        assert!(matches!(outcome, Outcome::FixedPoint(generations) if generations < 9));
+        assert_eq!(final_warehouse.to_string(), async_warehouse.to_string());
+    }
+
+    #[test]
+    fn test_automaton_life() {
+        let life = Automaton::new(LifeRule::from_str("B3/S23").unwrap());
+
+        let block = Warehouse::from_str(indoc! {"
+            ....
+            .@@.
+            .@@.
+            ....
+        "})
+        .unwrap();
+
+        assert_eq!(Outcome::FixedPoint(0), life.run(&block, 10).1);
+
+        let blinker = Warehouse::from_str(indoc! {"
+            .....
+            .....
+            .@@@.
+            .....
+            .....
+        "})
+        .unwrap();
+
+        let mut next = blinker.clone();
+        life.step(&mut next);
+
+        assert_eq!(
+            indoc! {"
+                .....
+                ..@..
+                .x@x.
+                ..@..
+                .....
+            "}
+            .trim_end(),
+            next.to_string()
+        );
+
+        assert_eq!(
+            Outcome::Cycle {
+                start: 0,
+                period: 2
+            },
+            life.run(&blinker, 10).1
+        );
+        assert_eq!(Outcome::Unsettled, life.run(&blinker, 1).1);
+
+        // A glider crosses an 8x8 torus diagonally every 32 generations
+        let glider = Warehouse::from_str(indoc! {"
+            .@......
+            ..@.....
+            @@@.....
+            ........
+            ........
+            ........
+            ........
+            ........
+        "})
+        .unwrap();
+
+        assert_eq!(
+            Outcome::Cycle {
+                start: 0,
+                period: 32
+            },
+            life.with_edges(EdgePolicy::Wrap).run(&glider, 100).1
+        );
+    }
+
+    #[test]
+    fn test_parse_life_rule() {
+        assert_eq!(
+            LifeRule {
+                birth: vec![3],
+                survival: vec![2, 3]
+            },
+            LifeRule::from_str("B3/S23").unwrap()
+        );
+
+        assert_eq!(
+            LifeRule {
+                birth: vec![3, 6],
+                survival: vec![]
+            },
+            LifeRule::from_str("s/b36").unwrap()
+        );
+
+        assert!(LifeRule::from_str("B3").is_err());
+        assert!(LifeRule::from_str("B3/S2x").is_err());
+        assert!(LifeRule::from_str("23/3").is_err());
+    }
+
+    #[test]
+    fn test_edge_policies() {
+        let warehouse = Warehouse::from_str(indoc! {"
+            @@@
+            @@@
+            @@@
+        "})
+        .unwrap();
+
+        // Corners go first, then edges, leaving the center with no neighbors
+        assert_eq!(9, warehouse.minimize(&MobilityRule::default()));
+
+        assert_eq!(
+            0,
+            warehouse.minimize(&MobilityRule::default().with_edges(EdgePolicy::Walls))
+        );
+
+        assert_eq!(
+            0,
+            warehouse.minimize(&MobilityRule::default().with_edges(EdgePolicy::Wrap))
+        );
+
+        // With wrapping, rolls on opposite edges are neighbors
+        let warehouse = Warehouse::from_str("@..@\n....\n").unwrap();
+        let rule = MobilityRule::default()
+            .with_threshold(1)
+            .with_neighborhood(&Neighborhood::VonNeumann);
+
+        assert_eq!(
+            vec![Position::new(0, 0), Position::new(3, 0)],
+            warehouse.movable_rolls(&rule)
+        );
+
+        assert!(
+            warehouse
+                .movable_rolls(&rule.with_edges(EdgePolicy::Wrap))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_neighborhoods() {
+        assert_eq!(
+            Neighborhood::Moore.offsets(),
+            Neighborhood::Radius(1).offsets()
+        );
+        assert_eq!(24, Neighborhood::Radius(2).offsets().len());
+
+        // Only rolls with a roll immediately to their right stay put, so the row erodes from the
+        // right one roll at a time
+        let rule = MobilityRule::default()
+            .with_threshold(1)
+            .with_neighborhood(&Neighborhood::Custom(vec![(1, 0)]));
+
+        assert_eq!(
+            vec![
+                vec![Position::new(3, 0)],
+                vec![Position::new(2, 0)],
+                vec![Position::new(1, 0)],
+                vec![Position::new(0, 0)],
+            ],
+            Warehouse::from_str("@@@@\n")
+                .unwrap()
+                .removal_rounds(&rule)
+                .map(|round| round.removed)
+                .collect::<Vec<_>>()
+        );
+
+        let von_neumann = MobilityRule::default().with_neighborhood(&Neighborhood::VonNeumann);
+
+        assert_eq!(
+            0,
+            Warehouse::from_str(TEST_WAREHOUSE)
+                .unwrap()
+                .minimize(&von_neumann.clone().with_threshold(0))
+        );
+
+        assert_eq!(
+            71,
+            Warehouse::from_str(TEST_WAREHOUSE)
+                .unwrap()
+                .minimize(&von_neumann.with_threshold(5))
+        );
+    }
+
+    #[test]
+    fn test_parse_neighborhood() {
+        assert_eq!(
+            Neighborhood::VonNeumann,
+            Neighborhood::from_str("von-neumann").unwrap()
+        );
+        assert_eq!(
+            Neighborhood::Moore,
+            Neighborhood::from_str("moore").unwrap()
+        );
+        assert_eq!(
+            Neighborhood::Radius(3),
+            Neighborhood::from_str("radius:3").unwrap()
+        );
+        assert_eq!(
+            Neighborhood::Custom(vec![(1, 0), (-2, 1)]),
+            Neighborhood::from_str("1,0/-2,1").unwrap()
+        );
+
+        assert!(Neighborhood::from_str("0,0").is_err());
+        assert!(Neighborhood::from_str("1").is_err());
+        assert!(Neighborhood::from_str("radius:x").is_err());
+    }
+
+    #[test]
+    fn test_parse_unknown_characters() {
+        assert_eq!(
+            "Unexpected character 'o' at line 2, column 3",
+            Warehouse::from_str("..@@\n@.o@\n")
+                .err()
+                .unwrap()
+                .to_string()
+        );
+
+        assert_eq!(
+            "Unexpected character ' ' at line 1, column 5",
+            Warehouse::from_str("..@@ \n@..@\n")
+                .err()
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_crlf() {
+        assert_eq!(
+            TEST_WAREHOUSE.trim_end(),
+            Warehouse::from_str(&TEST_WAREHOUSE.replace('\n', "\r\n"))
+                .unwrap()
+                .to_string()
+        );
+
+        // A stray carriage return in the middle of a line is still a typo
+        assert!(Warehouse::from_str("..@\r@\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_tile_symbols() {
+        let symbols = TileSymbols::from_str("empty=._/roll=@#").unwrap();
+
+        let warehouse = Warehouse::parse("#_.\n.@#\n", &symbols).unwrap();
+        assert_eq!("@..\n.@@", warehouse.to_string());
+
+        // Custom mappings replace the defaults
+        assert!(Warehouse::parse("x..\n", &symbols).is_err());
+
+        assert!(TileSymbols::from_str("empty=.#/roll=#").is_err());
+        assert!(TileSymbols::from_str("wall=#").is_err());
+        assert!(TileSymbols::from_str("roll").is_err());
+    }
+
+    #[test]
+    fn test_non_square_index() {
+        let warehouse = Warehouse::from_str(indoc! {"
+            @@@..
+            ...@.
+        "})
+        .unwrap();
+
+        assert_eq!(5, warehouse.tiles.width());
+        assert_eq!(2, warehouse.tiles.height());
+
+        let rolls: Vec<Position> = (0..2)
+            .flat_map(|y| (0..5).map(move |x| Position::new(x, y)))
+            .filter(|&position| matches!(warehouse[position], Tile::PaperRoll))
+            .collect();
+
+        assert_eq!(
+            vec![
+                Position::new(0, 0),
+                Position::new(1, 0),
+                Position::new(2, 0),
+                Position::new(3, 1)
+            ],
+            rolls
+        );
+    }
+
+    #[test]
+    fn test_wide_warehouse() {
+        let warehouse = indoc! {"
+            @@@@@@@@
+            @@@@@@@@
+            @@@@@@@@
+        "};
+
+        // Only the corners start out with fewer than four neighbors
+        assert_eq!(
+            vec![
+                Position::new(0, 0),
+                Position::new(0, 2),
+                Position::new(7, 0),
+                Position::new(7, 2)
+            ],
+            Warehouse::from_str(warehouse)
+                .unwrap()
+                .movable_rolls(&MobilityRule::default())
+        );
+
+        assert_eq!(
+            24,
+            Warehouse::from_str(warehouse)
+                .unwrap()
+                .minimize(&MobilityRule::default())
+        );
+    }
+
+    #[test]
+    fn test_tall_warehouse() {
+        let warehouse = indoc! {"
+            @@.
+            @@@
+            @@@
+            @@@
+            @@@
+            .@@
+        "};
+
+        assert_eq!(
+            vec![Position::new(0, 0), Position::new(2, 5)],
+            Warehouse::from_str(warehouse)
+                .unwrap()
+                .movable_rolls(&MobilityRule::default())
+        );
+
+        assert_eq!(
+            16,
+            Warehouse::from_str(warehouse)
+                .unwrap()
+                .minimize(&MobilityRule::default())
+        );
+    }
+
+    #[test]
+    fn test_ragged_warehouse() {
+        // Twelve tiles would pass a "multiple of the first line's width" check
+        assert!(Warehouse::from_str("@@@@\n@@\n@@@@@@\n").is_err());
+        assert!(Warehouse::from_str("@@@\n@@@@\n").is_err());
+        assert!(Warehouse::from_str("").is_err());
+        assert!(Warehouse::from_str("\n\n").is_err());
+
+        assert_eq!(
+            "Warehouse must be rectangular, but line 2 has 0 tiles instead of 4",
+            Warehouse::from_str("..@@\n\n@@@.\n")
+                .err()
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_trailing_blank_lines() {
+        assert_eq!(
+            "..@@\n@@@.",
+            Warehouse::from_str("..@@\n@@@.\n\n").unwrap().to_string()
+        );
+        assert_eq!(
+            "..@@\n@@@.",
+            Warehouse::from_str("..@@\r\n@@@.\r\n\r\n")
+                .unwrap()
+                .to_string()
+        );
     }
 }
@@ -1,3 +1,4 @@
+use advent_of_code_2025::intervals;
 use std::env;
 use std::error::Error;
 use std::fs::File;
@@ -64,31 +65,7 @@ impl IngredientDatabase {
     }
 
     pub fn possible_fresh_ingredients(&self) -> u64 {
-        let mut merged_ranges: Vec<RangeInclusive<u64>> = Vec::new();
-
-        let mut sorted_ranges = self.fresh_ranges.clone();
-        sorted_ranges.sort_by_key(|range| *range.start());
-        let sorted_ranges = sorted_ranges;
-
-        for range in sorted_ranges {
-            if merged_ranges
-                .last()
-                .map(|last_merged_range| last_merged_range.contains(range.start()))
-                .unwrap_or(false)
-            {
-                // The ranges overlap, so smoosh them together
-                let last_merged_range = merged_ranges.pop().unwrap();
-
-                merged_ranges.push(RangeInclusive::new(
-                    *last_merged_range.start().min(range.start()),
-                    *last_merged_range.end().max(range.end()),
-                ));
-            } else {
-                merged_ranges.push(range);
-            }
-        }
-
-        merged_ranges
+        intervals::merge_overlapping(self.fresh_ranges.iter().cloned())
             .iter()
             .map(|range| range.end() - range.start() + 1)
             .sum()
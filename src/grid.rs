@@ -0,0 +1,396 @@
+use std::error::Error;
+use std::ops::{Index, IndexMut};
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Position {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Position {
+    pub fn new(x: usize, y: usize) -> Self {
+        Position { x, y }
+    }
+
+    // Returns the position offset from this one by the given amount, or `None` if the result would
+    // have a negative coordinate
+    pub fn offset(&self, dx: isize, dy: isize) -> Option<Position> {
+        Some(Position {
+            x: self.x.checked_add_signed(dx)?,
+            y: self.y.checked_add_signed(dy)?,
+        })
+    }
+}
+
+impl From<(usize, usize)> for Position {
+    fn from((x, y): (usize, usize)) -> Self {
+        Position { x, y }
+    }
+}
+
+pub const VON_NEUMANN_OFFSETS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+pub const MOORE_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    // Cells in row-major order
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_cells(width: usize, cells: Vec<T>) -> Result<Self, Box<dyn Error>> {
+        if width == 0 {
+            return Err("Grid must have at least one column".into());
+        }
+
+        if cells.is_empty() {
+            return Err("Grid must have at least one row".into());
+        }
+
+        if !cells.len().is_multiple_of(width) {
+            return Err("Grid must be rectangular".into());
+        }
+
+        Ok(Grid {
+            width,
+            height: cells.len() / width,
+            cells,
+        })
+    }
+
+    pub fn from_rows(rows: impl IntoIterator<Item = Vec<T>>) -> Result<Self, Box<dyn Error>> {
+        let mut width = None;
+        let mut cells = Vec::new();
+
+        for (y, row) in rows.into_iter().enumerate() {
+            match width {
+                None => width = Some(row.len()),
+                Some(width) if width != row.len() => {
+                    return Err(format!(
+                        "Grid must be rectangular, but row {} has {} cells instead of {}",
+                        y,
+                        row.len(),
+                        width
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+
+            cells.extend(row);
+        }
+
+        Grid::from_cells(width.unwrap_or(0), cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        position.x < self.width && position.y < self.height
+    }
+
+    pub fn get(&self, position: Position) -> Option<&T> {
+        if self.contains(position) {
+            Some(&self.cells[self.cell_index(position)])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, position: Position) -> Option<&mut T> {
+        if self.contains(position) {
+            let index = self.cell_index(position);
+            Some(&mut self.cells[index])
+        } else {
+            None
+        }
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> + use<T> {
+        let width = self.width;
+
+        (0..self.cells.len()).map(move |i| Position::new(i % width, i / width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "Column out of bounds");
+
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    // Returns the in-bounds positions at the given offsets from `position`
+    pub fn neighbors<'a>(
+        &self,
+        position: Position,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = Position> + use<'a, T> {
+        let (width, height) = (self.width, self.height);
+
+        offsets
+            .iter()
+            .filter_map(move |(dx, dy)| position.offset(*dx, *dy))
+            .filter(move |neighbor| neighbor.x < width && neighbor.y < height)
+    }
+
+    // Returns the position at the given offset from `position`, wrapping around the edges of the
+    // grid
+    pub fn wrapping_offset(&self, position: Position, dx: isize, dy: isize) -> Position {
+        Position::new(
+            (position.x as isize + dx).rem_euclid(self.width as isize) as usize,
+            (position.y as isize + dy).rem_euclid(self.height as isize) as usize,
+        )
+    }
+
+    pub fn von_neumann_neighbors(
+        &self,
+        position: Position,
+    ) -> impl Iterator<Item = Position> + use<T> {
+        self.neighbors(position, &VON_NEUMANN_OFFSETS)
+    }
+
+    pub fn moore_neighbors(&self, position: Position) -> impl Iterator<Item = Position> + use<T> {
+        self.neighbors(position, &MOORE_OFFSETS)
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    fn cell_index(&self, position: Position) -> usize {
+        position.x + (position.y * self.width)
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Result<Self, Box<dyn Error>> {
+        Grid::from_cells(width, vec![value; width * height])
+    }
+
+    pub fn transpose(&self) -> Grid<T> {
+        self.remap(self.height, self.width, |position| {
+            Position::new(position.y, position.x)
+        })
+    }
+
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        self.remap(self.height, self.width, |position| {
+            Position::new(position.y, self.height - 1 - position.x)
+        })
+    }
+
+    pub fn rotate_counterclockwise(&self) -> Grid<T> {
+        self.remap(self.height, self.width, |position| {
+            Position::new(self.width - 1 - position.y, position.x)
+        })
+    }
+
+    // Builds a new grid of the given size where each cell is copied from the position in this grid
+    // returned by `source`
+    fn remap(&self, width: usize, height: usize, source: impl Fn(Position) -> Position) -> Grid<T> {
+        let cells = (0..width * height)
+            .map(|i| self[source(Position::new(i % width, i / width))].clone())
+            .collect();
+
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &Self::Output {
+        self.get(position).expect("Position out of bounds")
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut Self::Output {
+        self.get_mut(position).expect("Position out of bounds")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::grid::{Grid, Position};
+
+    // 1 2 3
+    // 4 5 6
+    fn test_grid() -> Grid<u32> {
+        Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap()
+    }
+
+    #[test]
+    fn test_from_rows() {
+        let grid = test_grid();
+
+        assert_eq!(3, grid.width());
+        assert_eq!(2, grid.height());
+
+        assert!(Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5]]).is_err());
+        assert!(Grid::<u32>::from_rows(vec![]).is_err());
+        assert!(Grid::<u32>::from_rows(vec![vec![]]).is_err());
+        assert!(Grid::from_cells(4, vec![0; 6]).is_err());
+    }
+
+    #[test]
+    fn test_index() {
+        let mut grid = test_grid();
+
+        assert_eq!(3, grid[Position::new(2, 0)]);
+        assert_eq!(4, grid[Position::new(0, 1)]);
+        assert_eq!(Some(&6), grid.get(Position::new(2, 1)));
+        assert_eq!(None, grid.get(Position::new(3, 0)));
+        assert_eq!(None, grid.get(Position::new(0, 2)));
+
+        grid[Position::new(1, 1)] = 50;
+        *grid.get_mut(Position::new(0, 0)).unwrap() = 10;
+
+        assert_eq!(
+            vec![10, 2, 3, 4, 50, 6],
+            grid.cells().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(None, grid.get_mut(Position::new(5, 5)));
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = test_grid();
+
+        assert_eq!(
+            vec![&[1, 2, 3][..], &[4, 5, 6][..]],
+            grid.rows().collect::<Vec<_>>()
+        );
+        assert_eq!(&[4, 5, 6], grid.row(1));
+
+        assert_eq!(
+            vec![vec![1, 4], vec![2, 5], vec![3, 6]],
+            grid.columns()
+                .map(|column| column.copied().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            vec![(Position::new(0, 0), &1), (Position::new(1, 0), &2)],
+            grid.iter().take(2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = test_grid();
+
+        assert_eq!(
+            vec![Position::new(1, 0), Position::new(0, 1)],
+            grid.von_neumann_neighbors(Position::new(0, 0))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            vec![
+                Position::new(0, 0),
+                Position::new(2, 0),
+                Position::new(0, 1),
+                Position::new(1, 1),
+                Position::new(2, 1),
+            ],
+            grid.moore_neighbors(Position::new(1, 0))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            vec![Position::new(2, 0)],
+            grid.neighbors(Position::new(0, 0), &[(2, 0), (3, 0), (-1, 0)])
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_wrapping_offset() {
+        let grid = test_grid();
+
+        assert_eq!(
+            Position::new(2, 1),
+            grid.wrapping_offset(Position::new(0, 0), -1, -1)
+        );
+        assert_eq!(
+            Position::new(1, 0),
+            grid.wrapping_offset(Position::new(2, 1), 5, 3)
+        );
+    }
+
+    #[test]
+    fn test_transpose_and_rotate() {
+        let grid = test_grid();
+
+        assert_eq!(
+            Grid::from_rows(vec![vec![1, 4], vec![2, 5], vec![3, 6]]).unwrap(),
+            grid.transpose()
+        );
+
+        assert_eq!(
+            Grid::from_rows(vec![vec![4, 1], vec![5, 2], vec![6, 3]]).unwrap(),
+            grid.rotate_clockwise()
+        );
+
+        assert_eq!(
+            Grid::from_rows(vec![vec![3, 6], vec![2, 5], vec![1, 4]]).unwrap(),
+            grid.rotate_counterclockwise()
+        );
+
+        assert_eq!(grid, grid.rotate_clockwise().rotate_counterclockwise());
+        assert_eq!(
+            grid,
+            grid.rotate_clockwise()
+                .rotate_clockwise()
+                .rotate_clockwise()
+                .rotate_clockwise()
+        );
+        assert_eq!(grid, grid.transpose().transpose());
+    }
+}
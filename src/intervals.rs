@@ -0,0 +1,48 @@
+use std::ops::RangeInclusive;
+
+pub fn merge_overlapping<T: Ord + Copy>(
+    ranges: impl IntoIterator<Item = RangeInclusive<T>>,
+) -> Vec<RangeInclusive<T>> {
+    let mut merged_ranges: Vec<RangeInclusive<T>> = Vec::new();
+
+    let mut sorted_ranges: Vec<RangeInclusive<T>> = ranges.into_iter().collect();
+    sorted_ranges.sort_by_key(|range| *range.start());
+    let sorted_ranges = sorted_ranges;
+
+    for range in sorted_ranges {
+        if merged_ranges
+            .last()
+            .map(|last_merged_range| last_merged_range.contains(range.start()))
+            .unwrap_or(false)
+        {
+            // The ranges overlap, so smoosh them together
+            let last_merged_range = merged_ranges.pop().unwrap();
+
+            merged_ranges.push(RangeInclusive::new(
+                *last_merged_range.start().min(range.start()),
+                *last_merged_range.end().max(range.end()),
+            ));
+        } else {
+            merged_ranges.push(range);
+        }
+    }
+
+    merged_ranges
+}
+
+#[cfg(test)]
+mod test {
+    use crate::intervals::merge_overlapping;
+
+    #[test]
+    fn test_merge_overlapping() {
+        assert_eq!(
+            vec![3..=5, 10..=20],
+            merge_overlapping(vec![3..=5, 10..=14, 16..=20, 12..=18])
+        );
+
+        assert_eq!(vec![1..=10], merge_overlapping(vec![1..=10, 2..=3, 4..=10]));
+        assert_eq!(vec![1..=2, 3..=4], merge_overlapping(vec![3..=4, 1..=2]));
+        assert!(merge_overlapping(Vec::<std::ops::RangeInclusive<u64>>::new()).is_empty());
+    }
+}
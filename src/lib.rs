@@ -0,0 +1,3 @@
+pub mod grid;
+pub mod intervals;
+pub mod random;
@@ -0,0 +1,39 @@
+// A tiny xorshift generator for randomized tests; it keeps them deterministic without pulling in
+// a dependency for random numbers. The returned closure yields values in `0..bound`.
+pub fn generator<T>(seed: u64) -> impl FnMut(T) -> T
+where
+    T: Into<u64> + TryFrom<u64>,
+{
+    assert_ne!(0, seed, "Xorshift generators get stuck at zero");
+
+    let mut state = seed;
+
+    move |bound| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+
+        T::try_from(state % bound.into())
+            .unwrap_or_else(|_| unreachable!("Values below the bound fit in its type"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::random::generator;
+
+    #[test]
+    fn test_generator() {
+        let mut next_random = generator::<u32>(0x2545_f491_4f6c_dd1d);
+        let values: Vec<u32> = (0..1000).map(|_| next_random(10)).collect();
+
+        assert!(values.iter().all(|&value| value < 10));
+        assert!((0..10).all(|value| values.contains(&value)));
+
+        let mut again = generator::<u32>(0x2545_f491_4f6c_dd1d);
+        assert_eq!(values, (0..1000).map(|_| again(10)).collect::<Vec<_>>());
+
+        let mut next_random = generator::<u64>(0x2545_f491_4f6c_dd1d);
+        assert!((0..1000).all(|_| next_random(u64::MAX) < u64::MAX));
+    }
+}